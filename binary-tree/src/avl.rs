@@ -0,0 +1,299 @@
+use std::{cmp, mem};
+
+type Link<T> = Option<Box<AvlNode<T>>>;
+
+/// A binary search tree that keeps itself balanced with AVL rotations,
+/// so the heights of the two subtrees of any node differ by at most
+/// one, and inserting sorted input doesn't degenerate into a list.
+pub struct AvlTree<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+struct AvlNode<T> {
+    element: T,
+    /// Number of nodes on the longest path from this node down to a leaf,
+    /// counting this node
+    height: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> Default for AvlTree<T> {
+    fn default() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+}
+
+impl<T> AvlTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of levels in the tree, which is 0 when empty
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+}
+
+impl<T: Clone> AvlTree<T> {
+    pub fn inorder_walk(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len);
+        inorder_walk(&self.root, &mut result);
+
+        result
+    }
+}
+
+impl<T: Ord> AvlTree<T> {
+    pub fn insert(&mut self, element: T) {
+        self.root = Some(insert(self.root.take(), element));
+        self.len += 1;
+    }
+
+    // Return a reference to the value in the tree if it exists
+    pub fn search(&self, element: &T) -> Option<&T> {
+        let mut link = &self.root;
+
+        while let Some(ref node) = *link {
+            if *element == node.element {
+                return Some(&node.element);
+            } else if *element < node.element {
+                link = &node.left;
+            } else {
+                link = &node.right;
+            }
+        }
+
+        None
+    }
+
+    // Remove an element equal to the given one from the tree and return it
+    pub fn remove(&mut self, element: &T) -> Option<T> {
+        let removed = remove(&mut self.root, element);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+}
+
+fn height<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn inorder_walk<T: Clone>(link: &Link<T>, result: &mut Vec<T>) {
+    if let Some(ref node) = *link {
+        inorder_walk(&node.left, result);
+        result.push(node.element.clone());
+        inorder_walk(&node.right, result);
+    }
+}
+
+impl<T> AvlNode<T> {
+    fn update_height(&mut self) {
+        self.height = 1 + cmp::max(height(&self.left), height(&self.right));
+    }
+
+    /// Height of the left subtree minus the height of the right subtree
+    fn balance_factor(&self) -> isize {
+        height(&self.left) as isize - height(&self.right) as isize
+    }
+}
+
+fn rotate_left<T>(mut node: Box<AvlNode<T>>) -> Box<AvlNode<T>> {
+    let mut pivot = node.right.take().expect("rotating left needs a right child");
+    node.right = pivot.left.take();
+    node.update_height();
+    pivot.left = Some(node);
+    pivot.update_height();
+
+    pivot
+}
+
+fn rotate_right<T>(mut node: Box<AvlNode<T>>) -> Box<AvlNode<T>> {
+    let mut pivot = node.left.take().expect("rotating right needs a left child");
+    node.left = pivot.right.take();
+    node.update_height();
+    pivot.right = Some(node);
+    pivot.update_height();
+
+    pivot
+}
+
+/// Restores the AVL property at a node whose subtrees are balanced but
+/// may differ in height by two, returning the new root of the subtree.
+fn rebalance<T>(mut node: Box<AvlNode<T>>) -> Box<AvlNode<T>> {
+    node.update_height();
+    let balance = node.balance_factor();
+
+    if balance > 1 {
+        if node.left.as_ref().map_or(0, |left| left.balance_factor()) < 0 {
+            node.left = node.left.take().map(rotate_left);
+        }
+        rotate_right(node)
+    } else if balance < -1 {
+        if node.right.as_ref().map_or(0, |right| right.balance_factor()) > 0 {
+            node.right = node.right.take().map(rotate_right);
+        }
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn insert<T: Ord>(link: Link<T>, element: T) -> Box<AvlNode<T>> {
+    match link {
+        None => Box::new(AvlNode {
+            element,
+            height: 1,
+            left: None,
+            right: None,
+        }),
+        Some(mut node) => {
+            if element <= node.element {
+                node.left = Some(insert(node.left.take(), element));
+            } else {
+                node.right = Some(insert(node.right.take(), element));
+            }
+
+            rebalance(node)
+        }
+    }
+}
+
+fn remove<T: Ord>(link: &mut Link<T>, element: &T) -> Option<T> {
+    let node = link.as_mut()?;
+
+    let removed = if *element < node.element {
+        remove(&mut node.left, element)
+    } else if *element > node.element {
+        remove(&mut node.right, element)
+    } else {
+        take_root(link)
+    };
+
+    *link = link.take().map(rebalance);
+    removed
+}
+
+/// Removes the smallest element from a subtree and returns it
+fn take_min<T>(link: &mut Link<T>) -> Option<T> {
+    let node = link.as_mut()?;
+
+    let removed = if node.left.is_some() {
+        take_min(&mut node.left)
+    } else {
+        take_root(link)
+    };
+
+    *link = link.take().map(rebalance);
+    removed
+}
+
+/// Removes the root element of a subtree and returns it, splicing the
+/// root's in-order successor into its place if it has two children.
+/// The caller is responsible for rebalancing the new root.
+fn take_root<T>(link: &mut Link<T>) -> Option<T> {
+    let mut node = link.take()?;
+
+    match (node.left.take(), node.right.take()) {
+        (None, right) => *link = right,
+        (left, None) => *link = left,
+        (left, mut right) => {
+            let successor = take_min(&mut right).expect("right subtree is non-empty");
+            let element = mem::replace(&mut node.element, successor);
+            node.left = left;
+            node.right = right;
+            *link = Some(node);
+            return Some(element);
+        }
+    }
+
+    Some(node.element)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Checks the AVL property and the stored heights, returning the
+    /// height of the subtree
+    fn check_balanced<T>(link: &Link<T>) -> usize {
+        match *link {
+            None => 0,
+            Some(ref node) => {
+                let left = check_balanced(&node.left);
+                let right = check_balanced(&node.right);
+                assert!(
+                    (left as isize - right as isize).abs() <= 1,
+                    "Subtrees differ by more than one level"
+                );
+                assert_eq!(node.height, 1 + cmp::max(left, right), "Stored height is stale");
+                node.height
+            }
+        }
+    }
+
+    #[test]
+    fn test_sorted_insert_stays_balanced() {
+        let mut tree = AvlTree::new();
+        for n in 0..1000 {
+            tree.insert(n);
+        }
+
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(check_balanced(&tree.root), tree.height());
+        assert!(tree.height() <= 14, "Height {} is not logarithmic", tree.height());
+        assert_eq!(tree.inorder_walk(), (0..1000).collect::<Vec<_>>());
+        assert_eq!(tree.search(&500), Some(&500));
+        assert_eq!(tree.search(&1000), None);
+    }
+
+    #[test]
+    fn test_remove_stays_balanced() {
+        let mut tree = AvlTree::new();
+        for n in (0..500).rev() {
+            tree.insert(n);
+        }
+
+        for n in (0..500).filter(|n| n % 3 != 0) {
+            assert_eq!(tree.remove(&n), Some(n));
+            check_balanced(&tree.root);
+        }
+
+        assert_eq!(tree.remove(&1), None);
+        assert_eq!(tree.len(), 167);
+        assert_eq!(tree.inorder_walk(), (0..500).filter(|n| n % 3 == 0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut tree = AvlTree::new();
+        for n in [2, 1, 2, 3, 2] {
+            tree.insert(n);
+        }
+        check_balanced(&tree.root);
+        assert_eq!(tree.inorder_walk(), vec![1, 2, 2, 2, 3]);
+
+        assert_eq!(tree.remove(&2), Some(2));
+        assert_eq!(tree.remove(&2), Some(2));
+        assert_eq!(tree.inorder_walk(), vec![1, 2, 3]);
+
+        for n in [1, 2, 3] {
+            tree.remove(&n);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+    }
+}
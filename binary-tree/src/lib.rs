@@ -1,49 +1,47 @@
-#![allow(dead_code)]
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
 
-enum BinaryTree<T> {
+mod avl;
+mod map;
+mod red_black;
+
+pub use avl::AvlTree;
+pub use map::BstMap;
+pub use red_black::RedBlackTree;
+
+/// An unbalanced binary search tree. The nodes hang off a separate
+/// `Tree` link type so that `BinaryTree` can free them iteratively when
+/// dropped, while the links can still be taken apart by value.
+pub struct BinaryTree<T> {
+    root: Tree<T>
+}
+
+enum Tree<T> {
     Empty,
     NonEmpty(Box<TreeNode<T>>)
 }
 
 struct TreeNode<T> {
     element: T,
-    left: BinaryTree<T>,
-    right: BinaryTree<T>
+    /// Number of nodes in the subtree rooted at this node, counting
+    /// this node
+    size: usize,
+    left: Tree<T>,
+    right: Tree<T>
 }
 
 #[test]
 fn test_hand_building_tree_of_planets() {
-    use self::BinaryTree::*;
-    let jupiter_tree = NonEmpty(Box::new(TreeNode {
-        element: "Jupiter",
-        left: Empty,
-        right: Empty
-    }));
-    let mercury_tree = NonEmpty(Box::new(TreeNode {
-        element: "Mercury",
-        left: Empty,
-        right: Empty
-    }));
-    let mars_tree = NonEmpty(Box::new(TreeNode {
-        element: "Mars",
-        left: jupiter_tree,
-        right: mercury_tree
-    }));
-    let venus_tree = NonEmpty(Box::new(TreeNode {
-        element: "Venus",
-        left: Empty,
-        right: Empty
-    }));
-    let uranus_tree = NonEmpty(Box::new(TreeNode {
-        element: "Uranus",
-        left: Empty,
-        right: venus_tree
-    }));
-    let tree = NonEmpty(Box::new(TreeNode {
-        element: "Saturn",
-        left: mars_tree,
-        right: uranus_tree
-    }));
+    let jupiter_tree = BinaryTree::leaf("Jupiter");
+    let mercury_tree = BinaryTree::leaf("Mercury");
+    let mars_tree = BinaryTree::new(jupiter_tree, "Mars", mercury_tree);
+    let venus_tree = BinaryTree::leaf("Venus");
+    let uranus_tree = BinaryTree::new(BinaryTree::empty(), "Uranus", venus_tree);
+    let tree = BinaryTree::new(mars_tree, "Saturn", uranus_tree);
+
+    assert_eq!(tree.len(), 6);
+    assert_eq!(tree.height(), 3);
 
     // Test traversal methods
     assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Mars", "Mercury", "Saturn", "Uranus", "Venus"]);
@@ -52,35 +50,467 @@ fn test_hand_building_tree_of_planets() {
 
     // Test iterator order 
     assert_eq!(tree.inorder_iter().collect::<Vec<_>>(), vec![&"Jupiter", &"Mars", &"Mercury", &"Saturn", &"Uranus", &"Venus"]);
-    // assert_eq!(tree.preorder_iter().collect::<Vec<_>>(), vec![&"Saturn", &"Mars", &"Jupiter", &"Mercury", &"Uranus", &"Venus"]);
-    // assert_eq!(tree.postorder_iter().collect::<Vec<_>>(), vec![&"Jupiter", &"Mercury", &"Mars", &"Venus", &"Uranus", &"Saturn"]);
+    assert_eq!(tree.preorder_iter().collect::<Vec<_>>(), vec![&"Saturn", &"Mars", &"Jupiter", &"Mercury", &"Uranus", &"Venus"]);
+    assert_eq!(tree.postorder_iter().collect::<Vec<_>>(), vec![&"Jupiter", &"Mercury", &"Mars", &"Venus", &"Uranus", &"Saturn"]);
+}
+
+impl<T> Default for BinaryTree<T> {
+    fn default() -> Self {
+        BinaryTree { root: Empty }
+    }
+}
+
+impl<T> Drop for BinaryTree<T> {
+    fn drop(&mut self) {
+        dismantle(mem::replace(&mut self.root, Empty));
+    }
 }
 
 impl<T> BinaryTree<T> {
-    pub fn new(left: Self, element: T, right: Self) -> Self {
-        NonEmpty(Box::new(TreeNode { left, element, right }))
+    /// Creates a tree with no elements
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tree holding a single element
+    pub fn leaf(element: T) -> Self {
+        Self::new(Self::empty(), element, Self::empty())
     }
 
-    fn inorder_iter(&self) -> TreeIter<T> {
+    /// Creates a tree with the given element at the root and the given
+    /// subtrees as its children. The caller is responsible for keeping
+    /// the left subtree's elements no greater than the root and the right
+    /// subtree's greater than it, or searches will miss elements.
+    pub fn new(mut left: Self, element: T, mut right: Self) -> Self {
+        let left = mem::replace(&mut left.root, Empty);
+        let right = mem::replace(&mut right.root, Empty);
+        let size = left.len() + right.len() + 1;
+
+        BinaryTree { root: NonEmpty(Box::new(TreeNode { element, size, left, right })) }
+    }
+
+    /// Returns the number of elements in the tree
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.root, Empty)
+    }
+
+    /// Returns the number of levels in the tree, which is 0 when empty
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
+
+    /// Returns an iterator over the elements in order
+    pub fn inorder_iter(&self) -> TreeIter<'_, T> {
         let mut iter = TreeIter { unvisited: Vec::new() };
-        iter.push_left_edge(self);
+        iter.push_left_edge(&self.root);
 
         iter
     }
 
-    fn preorder_iter(&self) -> TreeIter<T> {
-        let mut iter = TreeIter { unvisited: Vec::new() };
-        iter.unvisited.push(*self);
+    /// Returns an iterator that visits each node before its children
+    pub fn preorder_iter(&self) -> PreorderIter<'_, T> {
+        let mut iter = PreorderIter { unvisited: Vec::new() };
+        if let NonEmpty(ref node) = self.root {
+            iter.unvisited.push(node);
+        }
+
+        iter
+    }
+
+    /// Returns an iterator that visits each node after its children
+    pub fn postorder_iter(&self) -> PostorderIter<'_, T> {
+        let mut iter = PostorderIter { unvisited: Vec::new() };
+        if let NonEmpty(ref node) = self.root {
+            iter.unvisited.push((node, false));
+        }
+
+        iter
+    }
+
+    /// Returns an iterator over the elements in order, like `inorder_iter`
+    pub fn iter(&self) -> TreeIter<'_, T> {
+        self.inorder_iter()
+    }
+
+    /// Consumes the tree and returns its elements in order. Unlike
+    /// `inorder_walk`, the elements are moved out rather than cloned.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len());
+        result.extend(self);
+
+        result
+    }
+
+    /// Returns the smallest element in the tree
+    pub fn min(&self) -> Option<&T> {
+        let mut tree = &self.root;
+        let mut min = None;
+
+        while let NonEmpty(ref node) = *tree {
+            min = Some(&node.element);
+            tree = &node.left;
+        }
+
+        min
+    }
+
+    /// Returns the largest element in the tree
+    pub fn max(&self) -> Option<&T> {
+        let mut tree = &self.root;
+        let mut max = None;
+
+        while let NonEmpty(ref node) = *tree {
+            max = Some(&node.element);
+            tree = &node.right;
+        }
+
+        max
+    }
 
+    /// Returns the element that would be at index `k` of the tree's
+    /// sorted contents, so `kth(0)` is the smallest element and
+    /// `kth(len / 2)` the median. Takes time proportional to the height.
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        let mut tree = &self.root;
+
+        while let NonEmpty(ref node) = *tree {
+            let left = node.left.len();
+            if k < left {
+                tree = &node.left;
+            } else if k == left {
+                return Some(&node.element);
+            } else {
+                k -= left + 1;
+                tree = &node.right;
+            }
+        }
+
+        None
+    }
+
+    /// Rebalances the tree in place with the Day-Stout-Warren algorithm:
+    /// right rotations first flatten it into a sorted "vine" with no
+    /// left children, then rounds of left rotations along the vine fold
+    /// it into a tree whose height is the minimum possible for its size.
+    /// Runs in linear time and without allocating.
+    pub fn balance(&mut self) {
+        // flatten the tree into a vine
+        let mut tree = &mut self.root;
+        loop {
+            let has_left = match *tree {
+                NonEmpty(ref node) => matches!(node.left, NonEmpty(_)),
+                Empty => break,
+            };
+
+            if has_left {
+                rotate_right(tree);
+            } else {
+                tree = match *tree {
+                    NonEmpty(ref mut node) => &mut node.right,
+                    Empty => unreachable!("checked above"),
+                };
+            }
+        }
+
+        // leave the nodes that don't fit in a perfect tree as leaves at
+        // the bottom, then halve the vine until it's gone
+        let len = self.len();
+        let mut perfect = (1 << (len + 1).ilog2()) - 1;
+        compress(&mut self.root, len - perfect);
+        while perfect > 1 {
+            perfect /= 2;
+            compress(&mut self.root, perfect);
+        }
+    }
+
+    /// Returns an in-order iterator that allows modifying each element.
+    /// Changing elements in a way that alters their ordering breaks the
+    /// search tree.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let mut iter = IterMut { unvisited: Vec::new() };
+        iter.push_left_edge(&mut self.root);
         iter
     }
 }
 
 impl<T: Clone> BinaryTree<T> {
     pub fn inorder_walk(&self) -> Vec<T> {
+        self.root.inorder_walk()
+    }
+
+    pub fn postorder_walk(&self) -> Vec<T> {
+        self.root.postorder_walk()
+    }
+
+    pub fn preorder_walk(&self) -> Vec<T> {
+        self.root.preorder_walk()
+    }
+}
+
+impl<T: fmt::Display> BinaryTree<T> {
+    /// Renders the tree in the Graphviz DOT language, labelling each
+    /// node with its element. Nodes only have the edges to the children
+    /// they actually have, so a lone child's side isn't shown.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph BinaryTree {\n");
+        if let NonEmpty(ref node) = self.root {
+            node.write_dot(&mut dot);
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+// A step of writing a tree as DOT: a node to write along with its
+// parent's id, or an edge to write once the subtree it leads to is done
+enum DotStep<'a, T> {
+    Node(&'a TreeNode<T>, Option<usize>),
+    Edge(usize, usize),
+}
+
+impl<T: fmt::Display> TreeNode<T> {
+    // Append this subtree's nodes and edges to the given DOT output,
+    // numbering the nodes in preorder. An explicit stack stands in for
+    // recursion, so a deep tree can't overflow the call stack
+    fn write_dot(&self, dot: &mut String) {
+        let mut steps = vec![DotStep::Node(self, None)];
+        let mut next_id = 0;
+
+        while let Some(step) = steps.pop() {
+            match step {
+                DotStep::Edge(parent, child) => dot.push_str(&format!("    n{} -> n{};\n", parent, child)),
+                DotStep::Node(node, parent) => {
+                    let id = next_id;
+                    next_id += 1;
+
+                    let label = node.element.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+                    dot.push_str(&format!("    n{} [label=\"{}\"];\n", id, label));
+
+                    // the edge to this node goes under its children, so
+                    // it's written after its subtree, and the right child
+                    // goes under the left one
+                    if let Some(parent) = parent {
+                        steps.push(DotStep::Edge(parent, id));
+                    }
+                    for child in [&node.right, &node.left] {
+                        if let NonEmpty(ref child) = *child {
+                            steps.push(DotStep::Node(child, Some(id)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Write the children of this node as ASCII branches, left first. Like
+    // `write_dot`, this keeps an explicit stack instead of recursing
+    fn fmt_children(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // each child is paired with whether it's a right child, and the
+        // length of the prefix its line starts with
+        let mut unvisited = Vec::new();
+        let mut prefix = String::new();
+        self.push_children(&mut unvisited, 0);
+
+        while let Some((child, last, prefix_len)) = unvisited.pop() {
+            prefix.truncate(prefix_len);
+            let (branch, indent) = if last { ("`-- ", "    ") } else { ("|-- ", "|   ") };
+
+            match *child {
+                Empty => writeln!(f, "{}{}(none)", prefix, branch)?,
+                NonEmpty(ref node) => {
+                    writeln!(f, "{}{}{}", prefix, branch, node.element)?;
+                    prefix.push_str(indent);
+                    node.push_children(&mut unvisited, prefix.len());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Push the children of this node for `fmt_children` to draw, unless
+    // it has none
+    fn push_children<'a>(&'a self, unvisited: &mut Vec<(&'a Tree<T>, bool, usize)>, prefix_len: usize) {
+        if let (Empty, Empty) = (&self.left, &self.right) {
+            return;
+        }
+
+        // push the right child first so the left one is drawn first
+        unvisited.push((&self.right, true, prefix_len));
+        unvisited.push((&self.left, false, prefix_len));
+    }
+}
+
+/// Draws the shape of the tree with ASCII art, one element per line and
+/// each node's left child above its right child. Missing children of a
+/// node that has only one are drawn as `(none)`.
+impl<T: fmt::Display> fmt::Display for BinaryTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.root {
+            Empty => writeln!(f, "(empty)"),
+            NonEmpty(ref node) => {
+                writeln!(f, "{}", node.element)?;
+                node.fmt_children(f)
+            }
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinaryTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = BinaryTree::empty();
+        tree.extend(iter);
+
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for BinaryTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+impl<T: Ord> BinaryTree<T> {
+    pub fn insert(&mut self, element: T) {
+        // walk down to the empty link where the element belongs, counting
+        // it in the size of every subtree on the way
+        let mut tree = &mut self.root;
+        while let NonEmpty(ref mut node) = *tree {
+            node.size += 1;
+            if element <= node.element {
+                tree = &mut node.left;
+            } else {
+                tree = &mut node.right;
+            }
+        }
+
+        *tree = NonEmpty(Box::new(TreeNode {
+            element,
+            size: 1,
+            left: Empty,
+            right: Empty
+        }));
+    }
+
+    // Return a reference to the value in the tree if it exists
+    pub fn search(&self, element: &T) -> Option<&T> {
+        let mut tree = &self.root;
+        while let NonEmpty(ref node) = *tree {
+            if *element == node.element {
+                return Some(&node.element);
+            } else if *element < node.element {
+                tree = &node.left;
+            } else {
+                tree = &node.right;
+            }
+        }
+
+        None
+    }
+
+    // Return the largest element in the tree that is less than or equal
+    // to the given one
+    pub fn floor(&self, element: &T) -> Option<&T> {
+        let mut tree = &self.root;
+        let mut floor = None;
+
+        while let NonEmpty(ref node) = *tree {
+            if node.element <= *element {
+                floor = Some(&node.element);
+                tree = &node.right;
+            } else {
+                tree = &node.left;
+            }
+        }
+
+        floor
+    }
+
+    // Return the smallest element in the tree that is greater than or
+    // equal to the given one
+    pub fn ceiling(&self, element: &T) -> Option<&T> {
+        let mut tree = &self.root;
+        let mut ceiling = None;
+
+        while let NonEmpty(ref node) = *tree {
+            if node.element >= *element {
+                ceiling = Some(&node.element);
+                tree = &node.left;
+            } else {
+                tree = &node.right;
+            }
+        }
+
+        ceiling
+    }
+
+    // Return the number of elements in the tree that are less than the
+    // given one, which is the index it has or would have in sorted order
+    pub fn rank(&self, element: &T) -> usize {
+        let mut tree = &self.root;
+        let mut rank = 0;
+
+        while let NonEmpty(ref node) = *tree {
+            if node.element < *element {
+                rank += node.left.len() + 1;
+                tree = &node.right;
+            } else {
+                tree = &node.left;
+            }
+        }
+
+        rank
+    }
+
+    // Return the number of edges between the root and the first node
+    // holding an element equal to the given one, if there is such a node
+    pub fn depth(&self, element: &T) -> Option<usize> {
+        self.root.depth(element)
+    }
+
+    // Remove an element equal to the given one from the tree and return it
+    pub fn remove(&mut self, element: &T) -> Option<T> {
+        self.root.remove(element)
+    }
+}
+
+impl<T> Tree<T> {
+    fn len(&self) -> usize {
         match *self {
-            BinaryTree::Empty => vec![],
-            BinaryTree::NonEmpty(ref boxed) => {
+            Empty => 0,
+            NonEmpty(ref node) => node.size,
+        }
+    }
+
+    fn height(&self) -> usize {
+        // walk the nodes with an explicit stack, since a degenerate tree
+        // is too deep to recurse through
+        let mut height = 0;
+        let mut unvisited = vec![(self, 1)];
+        while let Some((tree, level)) = unvisited.pop() {
+            if let NonEmpty(ref node) = *tree {
+                height = height.max(level);
+                unvisited.push((&node.left, level + 1));
+                unvisited.push((&node.right, level + 1));
+            }
+        }
+        height
+    }
+}
+
+impl<T: Clone> Tree<T> {
+    fn inorder_walk(&self) -> Vec<T> {
+        match *self {
+            Tree::Empty => vec![],
+            Tree::NonEmpty(ref boxed) => {
                 let mut result = boxed.left.inorder_walk();
                 result.push(boxed.element.clone());
                 result.extend(boxed.right.inorder_walk());
@@ -90,10 +520,10 @@ impl<T: Clone> BinaryTree<T> {
         }
     }
 
-    pub fn postorder_walk(&self) -> Vec<T> {
+    fn postorder_walk(&self) -> Vec<T> {
         match *self {
-            BinaryTree::Empty => vec![],
-            BinaryTree::NonEmpty(ref boxed) => {
+            Tree::Empty => vec![],
+            Tree::NonEmpty(ref boxed) => {
                 let mut result = boxed.left.postorder_walk();
                 result.extend(boxed.right.postorder_walk());
                 result.push(boxed.element.clone());
@@ -103,10 +533,10 @@ impl<T: Clone> BinaryTree<T> {
         }
     }
 
-    pub fn preorder_walk(&self) -> Vec<T> {
+    fn preorder_walk(&self) -> Vec<T> {
         match *self {
-            BinaryTree::Empty => vec![],
-            BinaryTree::NonEmpty(ref boxed) => {
+            Tree::Empty => vec![],
+            Tree::NonEmpty(ref boxed) => {
                 let mut result = vec![];
                 result.push(boxed.element.clone());
                 result.extend(boxed.left.preorder_walk());
@@ -118,47 +548,166 @@ impl<T: Clone> BinaryTree<T> {
     }
 }
 
-impl<T: Ord> BinaryTree<T> {
-    pub fn insert(&mut self, element: T) {
-        match *self {
-            BinaryTree::Empty => {
-                *self = BinaryTree::NonEmpty(Box::new(TreeNode {
-                    element,
-                    left: BinaryTree::Empty,
-                    right: BinaryTree::Empty
-                }))
-            },
-            BinaryTree::NonEmpty(ref mut node) => {
-                if element <= node.element {
-                    node.left.insert(element);
-                } else {
-                    node.right.insert(element);
-                }
+impl<T: Ord> Tree<T> {
+    fn depth(&self, element: &T) -> Option<usize> {
+        let mut depth = 0;
+        let mut tree = self;
+        while let NonEmpty(ref node) = *tree {
+            if *element == node.element {
+                return Some(depth);
             }
+
+            depth += 1;
+            tree = if *element < node.element { &node.left } else { &node.right };
         }
+        None
     }
 
-    // Return a reference to the value in the tree if it exists
-    pub fn search(&self, element: &T) -> Option<&T> {
-        match *self {
-            BinaryTree::Empty => None,
-            BinaryTree::NonEmpty(ref node) => {
-                if *element == node.element {
-                    Some(&node.element)
-                } else if *element < node.element {
-                    node.left.search(element)
-                } else {
-                    node.right.search(element)
-                }
+    fn remove(&mut self, element: &T) -> Option<T> {
+        // make sure the element is there before shrinking the subtrees
+        // on the way down to it
+        let mut tree = &*self;
+        loop {
+            match *tree {
+                Empty => return None,
+                NonEmpty(ref node) if *element < node.element => tree = &node.left,
+                NonEmpty(ref node) if *element > node.element => tree = &node.right,
+                NonEmpty(_) => break,
+            }
+        }
+
+        let mut tree = self;
+        loop {
+            let found = match *tree {
+                NonEmpty(ref node) => *element == node.element,
+                Empty => unreachable!("the element was found above"),
+            };
+            if found {
+                return tree.take_root();
+            }
+
+            if let NonEmpty(ref mut node) = *tree {
+                node.size -= 1;
+                tree = if *element < node.element { &mut node.left } else { &mut node.right };
             }
         }
     }
+
+    // Remove the smallest element from the tree and return it
+    fn take_min(&mut self) -> Option<T> {
+        let mut tree = self;
+        loop {
+            match *tree {
+                Empty => return None,
+                NonEmpty(ref node) if matches!(node.left, Empty) => return tree.take_root(),
+                NonEmpty(_) => {}
+            }
+
+            if let NonEmpty(ref mut node) = *tree {
+                node.size -= 1;
+                tree = &mut node.left;
+            }
+        }
+    }
+
+    // Remove the root element of the tree and return it, splicing the
+    // root's in-order successor into its place if it has two children
+    fn take_root(&mut self) -> Option<T> {
+        let mut node = match mem::replace(self, Tree::Empty) {
+            Tree::Empty => return None,
+            Tree::NonEmpty(node) => node,
+        };
+
+        match (&node.left, &node.right) {
+            (Empty, _) => *self = mem::replace(&mut node.right, Empty),
+            (_, Empty) => *self = mem::replace(&mut node.left, Empty),
+            _ => {
+                let successor = node.right.take_min().expect("right subtree is non-empty");
+                let element = mem::replace(&mut node.element, successor);
+                node.size -= 1;
+                *self = NonEmpty(node);
+                return Some(element);
+            }
+        }
+
+        Some(node.element)
+    }
+}
+
+impl<T> TreeNode<T> {
+    fn update_size(&mut self) {
+        self.size = 1 + self.left.len() + self.right.len();
+    }
+}
+
+fn rotate_left<T>(tree: &mut Tree<T>) {
+    let mut node = match mem::replace(tree, Empty) {
+        NonEmpty(node) => node,
+        Empty => panic!("rotating left needs a node"),
+    };
+    let mut pivot = match mem::replace(&mut node.right, Empty) {
+        NonEmpty(pivot) => pivot,
+        Empty => panic!("rotating left needs a right child"),
+    };
+
+    node.right = mem::replace(&mut pivot.left, Empty);
+    node.update_size();
+    pivot.left = NonEmpty(node);
+    pivot.update_size();
+    *tree = NonEmpty(pivot);
+}
+
+fn rotate_right<T>(tree: &mut Tree<T>) {
+    let mut node = match mem::replace(tree, Empty) {
+        NonEmpty(node) => node,
+        Empty => panic!("rotating right needs a node"),
+    };
+    let mut pivot = match mem::replace(&mut node.left, Empty) {
+        NonEmpty(pivot) => pivot,
+        Empty => panic!("rotating right needs a left child"),
+    };
+
+    node.left = mem::replace(&mut pivot.right, Empty);
+    node.update_size();
+    pivot.right = NonEmpty(node);
+    pivot.update_size();
+    *tree = NonEmpty(pivot);
+}
+
+/// Rotates every other node of the vine starting at the given link to
+/// the left, `count` times, hanging the skipped nodes off the left of
+/// the ones that moved up
+fn compress<T>(mut tree: &mut Tree<T>, count: usize) {
+    for _ in 0..count {
+        rotate_left(tree);
+        tree = match *tree {
+            NonEmpty(ref mut node) => &mut node.right,
+            Empty => unreachable!("rotation leaves a node behind"),
+        };
+    }
+}
+
+/// Frees every node of a tree without recursing, however deep it is.
+/// Whenever the root has a left child, the tree is rotated right so the
+/// left spine shrinks by one; once it has none, the root is unlinked and
+/// dropped on its own, with its right subtree taking its place.
+fn dismantle<T>(mut tree: Tree<T>) {
+    while let NonEmpty(mut node) = tree {
+        tree = match mem::replace(&mut node.left, Empty) {
+            Empty => mem::replace(&mut node.right, Empty),
+            NonEmpty(mut left) => {
+                node.left = mem::replace(&mut left.right, Empty);
+                left.right = NonEmpty(node);
+                NonEmpty(left)
+            }
+        };
+    }
 }
 
 #[test]
 fn test_insert_method_1() {
     let planets = vec!["Mercury", "Venus", "Mars", "Jupiter", "Saturn", "Uranus"];
-    let mut tree = BinaryTree::Empty;
+    let mut tree = BinaryTree::empty();
     for planet in planets {
         tree.insert(planet);
     }
@@ -166,12 +715,21 @@ fn test_insert_method_1() {
     assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Mars", "Mercury", "Saturn", "Uranus", "Venus"]);
 }
 
+#[test]
+fn test_collect() {
+    let mut tree: BinaryTree<_> = ["Mercury", "Venus", "Mars"].iter().copied().collect();
+    tree.extend(["Jupiter", "Saturn", "Uranus"]);
+
+    assert_eq!(tree.len(), 6);
+    assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Mars", "Mercury", "Saturn", "Uranus", "Venus"]);
+}
+
 #[test]
 fn test_insert_method_2() {
-    let mut tree = BinaryTree::Empty;
+    let mut tree = BinaryTree::empty();
     tree.insert("Mercury");
     tree.insert("Venus");
-    for planet in vec!["Mars", "Jupiter", "Saturn", "Uranus"]  {
+    for planet in ["Mars", "Jupiter", "Saturn", "Uranus"] {
         tree.insert(planet);
     }
 
@@ -180,7 +738,7 @@ fn test_insert_method_2() {
 
 #[test]
 fn test_search_method() {
-    let mut tree = BinaryTree::Empty;
+    let mut tree = BinaryTree::empty();
     tree.insert("Pluto");
     tree.insert("Neptune");
     tree.insert("Saturn");
@@ -189,14 +747,393 @@ fn test_search_method() {
     assert_eq!(tree.search(&"Mercury"), None);
 }
 
-use self::BinaryTree::*;
+#[test]
+fn test_size_queries() {
+    let mut tree = BinaryTree::empty();
+    assert!(tree.is_empty());
+    assert_eq!(tree.len(), 0);
+    assert_eq!(tree.height(), 0);
+
+    for n in [4, 2, 6, 1, 3, 5, 7, 8] {
+        tree.insert(n);
+    }
+    assert!(!tree.is_empty());
+    assert_eq!(tree.len(), 8);
+    assert_eq!(tree.height(), 4);
+    assert_eq!(tree.depth(&4), Some(0));
+    assert_eq!(tree.depth(&3), Some(2));
+    assert_eq!(tree.depth(&8), Some(3));
+    assert_eq!(tree.depth(&9), None);
+
+    tree.remove(&9);
+    assert_eq!(tree.len(), 8, "Missing element leaves the count alone");
+    tree.remove(&4);
+    tree.remove(&8);
+    assert_eq!(tree.len(), 6);
+    assert_eq!(tree.height(), 3);
+
+    for n in 1..8 {
+        tree.remove(&n);
+    }
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn test_rendering() {
+    let mut tree = BinaryTree::empty();
+    assert_eq!(tree.to_string(), "(empty)\n");
+    assert_eq!(tree.to_dot(), "digraph BinaryTree {\n}\n");
+
+    for planet in ["Saturn", "Mars", "Uranus", "Jupiter", "Venus", "Mercury"] {
+        tree.insert(planet);
+    }
+
+    assert_eq!(tree.to_string(), "\
+Saturn
+|-- Mars
+|   |-- Jupiter
+|   `-- Mercury
+`-- Uranus
+    |-- (none)
+    `-- Venus
+");
+
+    assert_eq!(tree.to_dot(), "\
+digraph BinaryTree {
+    n0 [label=\"Saturn\"];
+    n1 [label=\"Mars\"];
+    n2 [label=\"Jupiter\"];
+    n1 -> n2;
+    n3 [label=\"Mercury\"];
+    n1 -> n3;
+    n0 -> n1;
+    n4 [label=\"Uranus\"];
+    n5 [label=\"Venus\"];
+    n4 -> n5;
+    n0 -> n4;
+}
+");
+
+    let quoted = BinaryTree::leaf("say \"hi\"");
+    assert!(quoted.to_dot().contains(r#"[label="say \"hi\""]"#));
+}
+
+#[test]
+fn test_into_sorted_vec() {
+    assert_eq!(BinaryTree::<String>::empty().into_sorted_vec(), Vec::<String>::new());
+
+    let mut tree = BinaryTree::empty();
+    for word in "tree sort moves every word out of the tree".split(' ') {
+        tree.insert(word.to_string());
+    }
+
+    let words = tree.into_sorted_vec();
+    assert_eq!(words, ["every", "moves", "of", "out", "sort", "the", "tree", "tree", "word"]);
+}
+
+#[test]
+fn test_nearest_queries() {
+    let mut tree = BinaryTree::empty();
+    assert_eq!(tree.min(), None);
+    assert_eq!(tree.max(), None);
+    assert_eq!(tree.floor(&5), None);
+    assert_eq!(tree.ceiling(&5), None);
+
+    for n in [40, 20, 60, 10, 30, 50, 70] {
+        tree.insert(n);
+    }
+    assert_eq!(tree.min(), Some(&10));
+    assert_eq!(tree.max(), Some(&70));
+
+    assert_eq!(tree.floor(&30), Some(&30));
+    assert_eq!(tree.floor(&45), Some(&40));
+    assert_eq!(tree.floor(&69), Some(&60));
+    assert_eq!(tree.floor(&5), None);
+
+    assert_eq!(tree.ceiling(&30), Some(&30));
+    assert_eq!(tree.ceiling(&45), Some(&50));
+    assert_eq!(tree.ceiling(&11), Some(&20));
+    assert_eq!(tree.ceiling(&75), None);
+}
+
+#[test]
+fn test_traversal_iterators() {
+    let mut tree = BinaryTree::empty();
+    assert_eq!(tree.preorder_iter().next(), None);
+    assert_eq!(tree.postorder_iter().next(), None);
+
+    for n in [4, 2, 6, 1, 3, 5, 7] {
+        tree.insert(n);
+    }
+
+    assert_eq!(tree.preorder_iter().copied().collect::<Vec<_>>(), tree.preorder_walk());
+    assert_eq!(tree.postorder_iter().copied().collect::<Vec<_>>(), tree.postorder_walk());
+    assert_eq!(tree.postorder_iter().copied().collect::<Vec<_>>(), vec![1, 3, 2, 5, 7, 6, 4]);
+}
+
+#[test]
+fn test_consuming_iterator() {
+    let mut tree = BinaryTree::empty();
+    for name in ["Saturn", "Mars", "Uranus", "Jupiter", "Venus", "Mercury"] {
+        tree.insert(name.to_string());
+    }
+
+    let names: Vec<String> = tree.into_iter().collect();
+    assert_eq!(names, ["Jupiter", "Mars", "Mercury", "Saturn", "Uranus", "Venus"]);
+
+    let mut tree = BinaryTree::empty();
+    for n in [2, 1, 3] {
+        tree.insert(Box::new(n));
+    }
+
+    let mut iter = tree.into_iter();
+    assert_eq!(iter.next(), Some(Box::new(1)));
+    assert_eq!(iter.next(), Some(Box::new(2)));
+    // dropping a partially consumed iterator frees the rest of the tree
+    drop(iter);
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut tree = BinaryTree::empty();
+    for word in ["the", "quick", "brown", "fox"] {
+        tree.insert((word, 0));
+    }
+
+    for text in ["the fox", "the brown dog", "the end"] {
+        for (word, count) in tree.iter_mut() {
+            *count += text.split(' ').filter(|w| w == word).count();
+        }
+    }
+
+    for (_, count) in &mut tree {
+        *count *= 10;
+    }
+
+    assert_eq!(tree.inorder_walk(), vec![("brown", 10), ("fox", 10), ("quick", 0), ("the", 30)]);
+}
+
+#[test]
+fn test_degenerate_tree() {
+    // sorted input makes the tree a single right spine
+    let mut tree = BinaryTree::empty();
+    for n in 0..20_000 {
+        tree.insert(n);
+    }
+
+    assert_eq!(tree.len(), 20_000);
+    assert_eq!(tree.search(&19_999), Some(&19_999));
+    assert_eq!(tree.search(&20_000), None);
+    assert_eq!(tree.max(), Some(&19_999));
+}
+
+#[test]
+fn test_drop_deep_trees() {
+    // hand-built spines, since inserting sorted input is quadratic
+    let mut left_spine = BinaryTree::empty();
+    let mut right_spine = BinaryTree::empty();
+    for n in 0..500_000 {
+        left_spine = BinaryTree::new(left_spine, n, BinaryTree::empty());
+        right_spine = BinaryTree::new(BinaryTree::empty(), -n, right_spine);
+    }
+    assert_eq!(left_spine.len(), 500_000);
+    assert_eq!(right_spine.min(), Some(&-499_999));
+    drop(left_spine);
 
-struct TreeIter<'a, T: 'a> {
+    let mut iter = right_spine.into_iter();
+    assert_eq!(iter.next(), Some(-499_999));
+    drop(iter);
+}
+
+#[test]
+fn test_height_and_depth_of_deep_trees() {
+    let mut left_spine = BinaryTree::empty();
+    let mut right_spine = BinaryTree::empty();
+    for n in 0..300_000 {
+        left_spine = BinaryTree::new(left_spine, n, BinaryTree::empty());
+        right_spine = BinaryTree::new(BinaryTree::empty(), -n, right_spine);
+    }
+    assert_eq!(left_spine.height(), 300_000);
+    assert_eq!(left_spine.depth(&0), Some(299_999));
+    assert_eq!(left_spine.depth(&-1), None);
+    assert_eq!(right_spine.height(), 300_000);
+    assert_eq!(right_spine.depth(&0), Some(299_999));
+    assert_eq!(right_spine.depth(&-299_999), Some(0));
+}
+
+#[test]
+fn test_to_dot_of_deep_trees() {
+    let mut left_spine = BinaryTree::empty();
+    for n in 0..300_000 {
+        left_spine = BinaryTree::new(left_spine, n, BinaryTree::empty());
+    }
+    let dot = left_spine.to_dot();
+    assert!(dot.starts_with("digraph BinaryTree {\n    n0 [label=\"299999\"];\n    n1 [label=\"299998\"];\n"));
+    assert!(dot.ends_with("    n1 -> n2;\n    n0 -> n1;\n}\n"));
+    assert_eq!(dot.lines().count(), 2 + 300_000 + 299_999);
+}
+
+#[test]
+fn test_display_of_deep_trees() {
+    // each line is indented by its depth, so the output grows with the
+    // square of the depth; a small stack keeps the tree shallow enough
+    std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| {
+            let mut right_spine = BinaryTree::empty();
+            for n in 0..2_000 {
+                right_spine = BinaryTree::new(BinaryTree::empty(), n, right_spine);
+            }
+            let drawn = right_spine.to_string();
+            assert!(drawn.starts_with("1999\n|-- (none)\n`-- 1998\n    |-- (none)\n"));
+            assert_eq!(drawn.lines().count(), 1 + 2 * 1_999);
+            assert_eq!(drawn.lines().last().map(str::trim_start), Some("`-- 0"));
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_remove_from_deep_trees() {
+    // a right spine of -299_999 down to 0
+    let mut right_spine = BinaryTree::empty();
+    for n in 0..300_000 {
+        right_spine = BinaryTree::new(BinaryTree::empty(), -n, right_spine);
+    }
+    assert_eq!(right_spine.remove(&0), Some(0));
+    assert_eq!(right_spine.remove(&1), None);
+    assert_eq!(right_spine.len(), 299_999);
+    assert_eq!(right_spine.max(), Some(&-1));
+
+    // removing the root finds its successor at the bottom of a left spine
+    let mut left_spine = BinaryTree::empty();
+    for n in 1..=300_000 {
+        left_spine = BinaryTree::new(left_spine, n, BinaryTree::empty());
+    }
+    let mut tree = BinaryTree::new(BinaryTree::leaf(-1), 0, left_spine);
+    assert_eq!(tree.remove(&0), Some(0));
+    assert_eq!(tree.len(), 300_001);
+    assert_eq!(tree.kth(1), Some(&1));
+    assert_eq!(tree.remove(&1), Some(1));
+    assert_eq!(tree.min(), Some(&-1));
+}
+
+#[test]
+fn test_order_statistics() {
+    let mut tree = BinaryTree::empty();
+    assert_eq!(tree.kth(0), None);
+    assert_eq!(tree.rank(&10), 0);
+
+    for n in [50, 30, 70, 20, 40, 60, 80, 30] {
+        tree.insert(n);
+    }
+
+    let sorted = tree.inorder_walk();
+    for (k, n) in sorted.iter().enumerate() {
+        assert_eq!(tree.kth(k), Some(n));
+    }
+    assert_eq!(tree.kth(8), None);
+    assert_eq!(tree.kth(tree.len() / 2), Some(&50), "Median");
+
+    assert_eq!(tree.rank(&20), 0);
+    assert_eq!(tree.rank(&30), 1);
+    assert_eq!(tree.rank(&35), 3, "Missing element ranks where it would go");
+    assert_eq!(tree.rank(&80), 7);
+    assert_eq!(tree.rank(&99), 8);
+
+    tree.remove(&30);
+    tree.remove(&50);
+    assert_eq!(tree.kth(1), Some(&30));
+    assert_eq!(tree.kth(3), Some(&60));
+    assert_eq!(tree.rank(&70), 4);
+}
+
+#[test]
+fn test_balance() {
+    let mut tree = BinaryTree::empty();
+    tree.balance();
+    assert!(tree.is_empty());
+
+    for n in 0..1000 {
+        tree.insert(n);
+    }
+    assert_eq!(tree.height(), 1000);
+
+    tree.balance();
+    assert_eq!(tree.height(), 10);
+    assert_eq!(tree.len(), 1000);
+    assert_eq!(tree.inorder_walk(), (0..1000).collect::<Vec<_>>());
+
+    // subtree sizes survive the rotations
+    for n in (0..1000).step_by(2) {
+        assert_eq!(tree.remove(&n), Some(n));
+    }
+    assert_eq!(tree.len(), 500);
+
+    let mut tree = BinaryTree::empty();
+    for n in [1, 2, 3, 4, 5, 6, 7] {
+        tree.insert(n);
+    }
+    tree.balance();
+    assert_eq!(tree.preorder_walk(), vec![4, 2, 1, 3, 6, 5, 7], "Perfect trees come out perfect");
+}
+
+#[test]
+fn test_remove_method() {
+    let mut tree = BinaryTree::empty();
+    for planet in ["Mercury", "Venus", "Mars", "Jupiter", "Saturn", "Uranus", "Earth"] {
+        tree.insert(planet);
+    }
+
+    // missing element
+    assert_eq!(tree.remove(&"Pluto"), None);
+
+    // leaf
+    assert_eq!(tree.remove(&"Earth"), Some("Earth"));
+    assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Mars", "Mercury", "Saturn", "Uranus", "Venus"]);
+
+    // one child
+    assert_eq!(tree.remove(&"Mars"), Some("Mars"));
+    assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Mercury", "Saturn", "Uranus", "Venus"]);
+
+    // two children, at the root
+    assert_eq!(tree.remove(&"Mercury"), Some("Mercury"));
+    assert_eq!(tree.inorder_walk(), vec!["Jupiter", "Saturn", "Uranus", "Venus"]);
+    assert_eq!(tree.preorder_walk(), vec!["Saturn", "Jupiter", "Venus", "Uranus"]);
+    assert_eq!(tree.search(&"Mercury"), None);
+
+    for planet in ["Jupiter", "Saturn", "Uranus", "Venus"] {
+        assert_eq!(tree.remove(&planet), Some(planet));
+    }
+    assert_eq!(tree.inorder_walk(), Vec::<&str>::new());
+    assert_eq!(tree.remove(&"Venus"), None);
+}
+
+#[test]
+fn test_remove_duplicates() {
+    let mut tree = BinaryTree::empty();
+    for n in [5, 3, 5, 8, 5, 7] {
+        tree.insert(n);
+    }
+
+    assert_eq!(tree.remove(&5), Some(5));
+    assert_eq!(tree.inorder_walk(), vec![3, 5, 5, 7, 8]);
+    assert_eq!(tree.remove(&5), Some(5));
+    assert_eq!(tree.remove(&5), Some(5));
+    assert_eq!(tree.inorder_walk(), vec![3, 7, 8]);
+    assert_eq!(tree.remove(&5), None);
+}
+
+use self::Tree::*;
+
+/// An in-order iterator over the elements of a `BinaryTree`
+pub struct TreeIter<'a, T: 'a> {
     unvisited: Vec<&'a TreeNode<T>>
 }
 
 impl<'a, T: 'a> TreeIter<'a, T> {
-    fn push_left_edge(&mut self, mut tree: &'a BinaryTree<T>) {
+    fn push_left_edge(&mut self, mut tree: &'a Tree<T>) {
         while let NonEmpty(ref node) = *tree {
             self.unvisited.push(node);
             tree = &node.left;
@@ -217,10 +1154,7 @@ impl<'a, T> Iterator for TreeIter<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<&'a T> {
-        let node = match self.unvisited.pop() {
-            None => return None,
-            Some(n) => n,
-        };
+        let node = self.unvisited.pop()?;
 
         self.push_left_edge(&node.right);
 
@@ -228,11 +1162,150 @@ impl<'a, T> Iterator for TreeIter<'a, T> {
     }
 }
 
+impl<T> IntoIterator for BinaryTree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let mut iter = IntoIter { unvisited: Vec::new() };
+        iter.push_left_edge(mem::replace(&mut self.root, Empty));
+        iter
+    }
+}
+
+impl<'a, T: 'a> IntoIterator for &'a mut BinaryTree<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An in-order iterator over mutable references to the elements of a
+/// `BinaryTree`
+pub struct IterMut<'a, T: 'a> {
+    // each visited node is split into its element and its right subtree,
+    // since its left subtree has already been pushed
+    unvisited: Vec<(&'a mut T, &'a mut Tree<T>)>
+}
+
+impl<'a, T: 'a> IterMut<'a, T> {
+    fn push_left_edge(&mut self, mut tree: &'a mut Tree<T>) {
+        while let NonEmpty(ref mut node) = *tree {
+            let TreeNode { element, left, right, .. } = &mut **node;
+            self.unvisited.push((element, right));
+            tree = left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let (element, right) = self.unvisited.pop()?;
+
+        self.push_left_edge(right);
+
+        Some(element)
+    }
+}
+
+/// Consumes the tree in order, moving each element out as it is visited
+pub struct IntoIter<T> {
+    unvisited: Vec<Box<TreeNode<T>>>
+}
+
+impl<T> IntoIter<T> {
+    fn push_left_edge(&mut self, mut tree: Tree<T>) {
+        while let NonEmpty(mut node) = tree {
+            tree = mem::replace(&mut node.left, Empty);
+            self.unvisited.push(node);
+        }
+    }
+}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        // the left subtrees of unvisited nodes are already on the stack,
+        // but their right subtrees may still be arbitrarily deep
+        for node in self.unvisited.drain(..) {
+            dismantle(NonEmpty(node));
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let node = self.unvisited.pop()?;
+        let TreeNode { element, right, .. } = *node;
+
+        self.push_left_edge(right);
+
+        Some(element)
+    }
+}
+
+/// A preorder iterator over the elements of a `BinaryTree`
+pub struct PreorderIter<'a, T: 'a> {
+    unvisited: Vec<&'a TreeNode<T>>
+}
+
+impl<'a, T> Iterator for PreorderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.unvisited.pop()?;
+
+        // push the right child first so the left subtree is visited first
+        if let NonEmpty(ref right) = node.right {
+            self.unvisited.push(right);
+        }
+        if let NonEmpty(ref left) = node.left {
+            self.unvisited.push(left);
+        }
+
+        Some(&node.element)
+    }
+}
+
+/// A postorder iterator over the elements of a `BinaryTree`
+pub struct PostorderIter<'a, T: 'a> {
+    // each node is paired with whether its children have been pushed yet
+    unvisited: Vec<(&'a TreeNode<T>, bool)>
+}
+
+impl<'a, T> Iterator for PostorderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let (node, expanded) = self.unvisited.pop()?;
+
+            if expanded {
+                return Some(&node.element);
+            }
+
+            self.unvisited.push((node, true));
+            if let NonEmpty(ref right) = node.right {
+                self.unvisited.push((right, false));
+            }
+            if let NonEmpty(ref left) = node.left {
+                self.unvisited.push((left, false));
+            }
+        }
+    }
+}
+
 #[test]
+#[allow(clippy::while_let_on_iterator)]
 fn external_iterator() {
-    let subtree_l = BinaryTree::new(Empty, "mecha", Empty);
-    let subtree_rl = BinaryTree::new(Empty, "droid", Empty);
-    let subtree_r = BinaryTree::new(subtree_rl, "robot", Empty);
+    let subtree_l = BinaryTree::leaf("mecha");
+    let subtree_rl = BinaryTree::leaf("droid");
+    let subtree_r = BinaryTree::new(subtree_rl, "robot", BinaryTree::empty());
     let tree = BinaryTree::new(subtree_l, "Jaeger", subtree_r);
 
     let mut v = Vec::new();
@@ -241,13 +1314,13 @@ fn external_iterator() {
     }
     assert_eq!(v, ["mecha", "Jaeger", "droid", "robot"]);
 
-    let left_subtree = BinaryTree::new(Empty, "mecha", Empty);
-    let right_subtree = BinaryTree::new(BinaryTree::new(Empty, "droid", Empty), "robot", Empty);
+    let left_subtree = BinaryTree::leaf("mecha");
+    let right_subtree = BinaryTree::new(BinaryTree::leaf("droid"), "robot", BinaryTree::empty());
     let tree = BinaryTree::new(left_subtree, "Jaeger", right_subtree);
 
     let mut v = Vec::new();
     let mut iter = TreeIter { unvisited: vec![] };
-    iter.push_left_edge(&tree);
+    iter.push_left_edge(&tree.root);
 
     for kind in iter {
         v.push(*kind);
@@ -255,7 +1328,7 @@ fn external_iterator() {
     assert_eq!(v, ["mecha", "Jaeger", "droid", "robot"]);
 
     let mut v = Vec::new();
-    let mut state = tree.into_iter();
+    let mut state = (&tree).into_iter();
     while let Some(kind) = state.next() {
         v.push(*kind);
     }
@@ -266,7 +1339,7 @@ fn external_iterator() {
                 .collect::<Vec<_>>(),
                 vec!["mega-mecha", "mega-Jaeger", "mega-droid", "mega-robot"]);
 
-    let mut iterator = tree.into_iter();
+    let mut iterator = (&tree).into_iter();
     assert_eq!(iterator.next(), Some(&"mecha"));
     assert_eq!(iterator.next(), Some(&"Jaeger"));
     assert_eq!(iterator.next(), Some(&"droid"));
@@ -0,0 +1,300 @@
+use std::mem;
+
+type Link<K, V> = Option<Box<MapNode<K, V>>>;
+
+/// An unbalanced binary search tree that associates each key with a
+/// value. Unlike `BinaryTree`, keys are unique: inserting a key that is
+/// already present replaces its value.
+pub struct BstMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+struct MapNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Default for BstMap<K, V> {
+    fn default() -> Self {
+        BstMap { root: None, len: 0 }
+    }
+}
+
+impl<K, V> Drop for BstMap<K, V> {
+    fn drop(&mut self) {
+        dismantle(self.root.take());
+    }
+}
+
+impl<K, V> BstMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the entries of the map, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { unvisited: Vec::new() };
+        iter.push_left_edge(&self.root);
+
+        iter
+    }
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    /// Associates the value with the key, returning the value that was
+    /// previously associated with it, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut link = &mut self.root;
+
+        while let Some(ref mut node) = *link {
+            if key == node.key {
+                return Some(mem::replace(&mut node.value, value));
+            } else if key < node.key {
+                link = &mut node.left;
+            } else {
+                link = &mut node.right;
+            }
+        }
+
+        *link = Some(Box::new(MapNode {
+            key,
+            value,
+            left: None,
+            right: None,
+        }));
+        self.len += 1;
+
+        None
+    }
+
+    // Return a reference to the value associated with the key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut link = &self.root;
+
+        while let Some(ref node) = *link {
+            if *key == node.key {
+                return Some(&node.value);
+            } else if *key < node.key {
+                link = &node.left;
+            } else {
+                link = &node.right;
+            }
+        }
+
+        None
+    }
+
+    // Return a mutable reference to the value associated with the key
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut link = &mut self.root;
+
+        while let Some(ref mut node) = *link {
+            if *key == node.key {
+                return Some(&mut node.value);
+            } else if *key < node.key {
+                link = &mut node.left;
+            } else {
+                link = &mut node.right;
+            }
+        }
+
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Remove the entry with the given key from the map and return its value
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let mut link = &mut self.root;
+
+        loop {
+            let node = match *link {
+                None => return None,
+                Some(ref node) if *key == node.key => break,
+                Some(_) => link.as_mut().expect("checked above"),
+            };
+
+            link = if *key < node.key { &mut node.left } else { &mut node.right };
+        }
+
+        self.len -= 1;
+        take_root(link).map(|(_, value)| value)
+    }
+}
+
+/// Removes the entry with the smallest key from a subtree and returns it
+fn take_min<K, V>(mut link: &mut Link<K, V>) -> Option<(K, V)> {
+    while link.as_ref()?.left.is_some() {
+        link = &mut link.as_mut().expect("checked above").left;
+    }
+
+    take_root(link)
+}
+
+/// Removes the root entry of a subtree and returns it, splicing the
+/// root's in-order successor into its place if it has two children
+fn take_root<K, V>(link: &mut Link<K, V>) -> Option<(K, V)> {
+    let mut node = link.take()?;
+
+    match (node.left.take(), node.right.take()) {
+        (None, right) => *link = right,
+        (left, None) => *link = left,
+        (left, mut right) => {
+            let (key, value) = take_min(&mut right).expect("right subtree is non-empty");
+            let key = mem::replace(&mut node.key, key);
+            let value = mem::replace(&mut node.value, value);
+            node.left = left;
+            node.right = right;
+            *link = Some(node);
+            return Some((key, value));
+        }
+    }
+
+    Some((node.key, node.value))
+}
+
+/// Frees every node of a subtree without recursing, the same way as the
+/// `dismantle` of `BinaryTree`: a root with a left child is rotated right,
+/// and a root without one is dropped with its right subtree replacing it.
+fn dismantle<K, V>(mut link: Link<K, V>) {
+    while let Some(mut node) = link {
+        link = match node.left.take() {
+            None => node.right.take(),
+            Some(mut left) => {
+                node.left = left.right.take();
+                left.right = Some(node);
+                Some(left)
+            }
+        };
+    }
+}
+
+impl<'a, K: 'a, V: 'a> IntoIterator for &'a BstMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An in-order iterator over the entries of a `BstMap`
+pub struct Iter<'a, K: 'a, V: 'a> {
+    unvisited: Vec<&'a MapNode<K, V>>,
+}
+
+impl<'a, K: 'a, V: 'a> Iter<'a, K, V> {
+    fn push_left_edge(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(ref node) = *link {
+            self.unvisited.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.unvisited.pop()?;
+
+        self.push_left_edge(&node.right);
+
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_get() {
+        let mut map = BstMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&"Mars"), None);
+
+        assert_eq!(map.insert("Mars", 2), None);
+        assert_eq!(map.insert("Earth", 1), None);
+        assert_eq!(map.insert("Venus", 0), None);
+        assert_eq!(map.insert("Mars", 3), Some(2), "Existing value replaced");
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.get(&"Mars"), Some(&3));
+        assert!(map.contains_key(&"Venus"));
+        assert!(!map.contains_key(&"Pluto"));
+
+        *map.get_mut(&"Earth").unwrap() += 10;
+        assert_eq!(map.get(&"Earth"), Some(&11));
+        assert_eq!(map.get_mut(&"Pluto"), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = BstMap::new();
+        for (key, value) in [(50, 'a'), (30, 'b'), (70, 'c'), (20, 'd'), (40, 'e'), (60, 'f'), (80, 'g')] {
+            map.insert(key, value);
+        }
+
+        assert_eq!(map.remove(&30), Some('b'), "Node with two children");
+        assert_eq!(map.remove(&50), Some('a'), "Root");
+        assert_eq!(map.remove(&80), Some('g'), "Leaf");
+        assert_eq!(map.remove(&80), None);
+        assert_eq!(map.len(), 4);
+
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, [(20, 'd'), (40, 'e'), (60, 'f'), (70, 'c')]);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut map = BstMap::new();
+        for word in "the quick brown fox jumps over the lazy dog".split(' ') {
+            match map.get_mut(&word) {
+                Some(count) => *count += 1,
+                None => {
+                    map.insert(word, 1);
+                }
+            }
+        }
+
+        let mut counts = Vec::new();
+        for (word, count) in &map {
+            counts.push((*word, *count));
+        }
+        assert_eq!(
+            counts,
+            [("brown", 1), ("dog", 1), ("fox", 1), ("jumps", 1), ("lazy", 1), ("over", 1), ("quick", 1), ("the", 2)]
+        );
+    }
+
+    #[test]
+    fn test_drop_deep_maps() {
+        // a hand-built map like the one sorted keys would give, since
+        // inserting them is quadratic
+        let mut map = BstMap::new();
+        for key in (0..500_000).rev() {
+            let right = map.root.take();
+            map.root = Some(Box::new(MapNode { key, value: key, left: None, right }));
+            map.len += 1;
+        }
+        assert_eq!(map.get(&499_999), Some(&499_999));
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.len(), 499_999);
+        drop(map);
+    }
+}
@@ -0,0 +1,589 @@
+/// Index of the shared black sentinel that stands in for every missing
+/// child, and for the parent of the root.
+const NIL: usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Black,
+}
+
+/// A binary search tree balanced with the classic red-black rules: the
+/// root is black, a red node never has a red child, and every path from
+/// a node down to a leaf passes through the same number of black nodes.
+/// Rebalancing after an insert or removal takes at most a constant number
+/// of rotations, which makes writes cheaper than in an `AvlTree`.
+///
+/// Nodes live in an arena and refer to each other by index, so the
+/// parent links the fixup rules rely on don't need `unsafe`.
+pub struct RedBlackTree<T> {
+    /// Slot `NIL` holds the sentinel; slots vacated by removals are
+    /// reused by later insertions.
+    nodes: Vec<RbNode<T>>,
+    root: usize,
+    /// Indices of vacated slots
+    free: Vec<usize>,
+    len: usize,
+}
+
+struct RbNode<T> {
+    /// The element stored at this node, or `None` for the sentinel and
+    /// vacated slots
+    element: Option<T>,
+    color: Color,
+    parent: usize,
+    left: usize,
+    right: usize,
+}
+
+impl<T> Default for RedBlackTree<T> {
+    fn default() -> Self {
+        RedBlackTree {
+            nodes: vec![RbNode {
+                element: None,
+                color: Color::Black,
+                parent: NIL,
+                left: NIL,
+                right: NIL,
+            }],
+            root: NIL,
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> RedBlackTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn element(&self, index: usize) -> &T {
+        self.nodes[index].element.as_ref().expect("linked nodes hold an element")
+    }
+
+    fn color(&self, index: usize) -> Color {
+        self.nodes[index].color
+    }
+
+    fn set_color(&mut self, index: usize, color: Color) {
+        self.nodes[index].color = color;
+    }
+
+    fn parent(&self, index: usize) -> usize {
+        self.nodes[index].parent
+    }
+
+    fn left(&self, index: usize) -> usize {
+        self.nodes[index].left
+    }
+
+    fn right(&self, index: usize) -> usize {
+        self.nodes[index].right
+    }
+
+    /// Index of the leftmost node in the subtree rooted at `index`
+    fn minimum(&self, mut index: usize) -> usize {
+        while self.left(index) != NIL {
+            index = self.left(index);
+        }
+
+        index
+    }
+
+    /// Replaces the link from `old`'s parent to `old` with a link to `new`
+    fn replace_child(&mut self, old: usize, new: usize) {
+        let parent = self.parent(old);
+
+        if parent == NIL {
+            self.root = new;
+        } else if old == self.left(parent) {
+            self.nodes[parent].left = new;
+        } else {
+            self.nodes[parent].right = new;
+        }
+    }
+
+    fn rotate_left(&mut self, x: usize) {
+        let y = self.right(x);
+        let inner = self.left(y);
+
+        self.nodes[x].right = inner;
+        if inner != NIL {
+            self.nodes[inner].parent = x;
+        }
+
+        self.nodes[y].parent = self.parent(x);
+        self.replace_child(x, y);
+        self.nodes[y].left = x;
+        self.nodes[x].parent = y;
+    }
+
+    fn rotate_right(&mut self, x: usize) {
+        let y = self.left(x);
+        let inner = self.right(y);
+
+        self.nodes[x].left = inner;
+        if inner != NIL {
+            self.nodes[inner].parent = x;
+        }
+
+        self.nodes[y].parent = self.parent(x);
+        self.replace_child(x, y);
+        self.nodes[y].right = x;
+        self.nodes[x].parent = y;
+    }
+
+    /// Puts `new` in `old`'s place under `old`'s parent. `new` may be the
+    /// sentinel, whose parent is then set so the removal fixup can walk
+    /// up from it.
+    fn transplant(&mut self, old: usize, new: usize) {
+        self.replace_child(old, new);
+        self.nodes[new].parent = self.parent(old);
+    }
+
+    /// Restores the red-black rules after `z` was linked in as a red leaf
+    fn insert_fixup(&mut self, mut z: usize) {
+        while self.color(self.parent(z)) == Color::Red {
+            let parent = self.parent(z);
+            let grandparent = self.parent(parent);
+
+            if parent == self.left(grandparent) {
+                let uncle = self.right(grandparent);
+
+                if self.color(uncle) == Color::Red {
+                    self.set_color(parent, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    z = grandparent;
+                } else {
+                    if z == self.right(parent) {
+                        z = parent;
+                        self.rotate_left(z);
+                    }
+                    let parent = self.parent(z);
+                    let grandparent = self.parent(parent);
+                    self.set_color(parent, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    self.rotate_right(grandparent);
+                }
+            } else {
+                let uncle = self.left(grandparent);
+
+                if self.color(uncle) == Color::Red {
+                    self.set_color(parent, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    z = grandparent;
+                } else {
+                    if z == self.left(parent) {
+                        z = parent;
+                        self.rotate_right(z);
+                    }
+                    let parent = self.parent(z);
+                    let grandparent = self.parent(parent);
+                    self.set_color(parent, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    self.rotate_left(grandparent);
+                }
+            }
+        }
+
+        let root = self.root;
+        self.set_color(root, Color::Black);
+    }
+
+    /// Restores the red-black rules after a black node was unlinked from
+    /// above `x`, leaving `x` with one black too few on its paths
+    fn remove_fixup(&mut self, mut x: usize) {
+        while x != self.root && self.color(x) == Color::Black {
+            let parent = self.parent(x);
+
+            if x == self.left(parent) {
+                let mut sibling = self.right(parent);
+
+                if self.color(sibling) == Color::Red {
+                    self.set_color(sibling, Color::Black);
+                    self.set_color(parent, Color::Red);
+                    self.rotate_left(parent);
+                    sibling = self.right(parent);
+                }
+
+                if self.color(self.left(sibling)) == Color::Black
+                    && self.color(self.right(sibling)) == Color::Black
+                {
+                    self.set_color(sibling, Color::Red);
+                    x = parent;
+                } else {
+                    if self.color(self.right(sibling)) == Color::Black {
+                        let nephew = self.left(sibling);
+                        self.set_color(nephew, Color::Black);
+                        self.set_color(sibling, Color::Red);
+                        self.rotate_right(sibling);
+                        sibling = self.right(parent);
+                    }
+
+                    let nephew = self.right(sibling);
+                    self.set_color(sibling, self.color(parent));
+                    self.set_color(parent, Color::Black);
+                    self.set_color(nephew, Color::Black);
+                    self.rotate_left(parent);
+                    x = self.root;
+                }
+            } else {
+                let mut sibling = self.left(parent);
+
+                if self.color(sibling) == Color::Red {
+                    self.set_color(sibling, Color::Black);
+                    self.set_color(parent, Color::Red);
+                    self.rotate_right(parent);
+                    sibling = self.left(parent);
+                }
+
+                if self.color(self.left(sibling)) == Color::Black
+                    && self.color(self.right(sibling)) == Color::Black
+                {
+                    self.set_color(sibling, Color::Red);
+                    x = parent;
+                } else {
+                    if self.color(self.left(sibling)) == Color::Black {
+                        let nephew = self.right(sibling);
+                        self.set_color(nephew, Color::Black);
+                        self.set_color(sibling, Color::Red);
+                        self.rotate_left(sibling);
+                        sibling = self.left(parent);
+                    }
+
+                    let nephew = self.left(sibling);
+                    self.set_color(sibling, self.color(parent));
+                    self.set_color(parent, Color::Black);
+                    self.set_color(nephew, Color::Black);
+                    self.rotate_right(parent);
+                    x = self.root;
+                }
+            }
+        }
+
+        self.set_color(x, Color::Black);
+    }
+
+    /// Unlinks the node at `z` from the tree, vacates its slot, and
+    /// returns its element
+    fn remove_index(&mut self, z: usize) -> T {
+        let mut removed_color = self.color(z);
+        let x;
+
+        if self.left(z) == NIL {
+            x = self.right(z);
+            self.transplant(z, x);
+        } else if self.right(z) == NIL {
+            x = self.left(z);
+            self.transplant(z, x);
+        } else {
+            // splice z's in-order successor into its place
+            let y = self.minimum(self.right(z));
+            removed_color = self.color(y);
+            x = self.right(y);
+
+            if self.parent(y) == z {
+                self.nodes[x].parent = y;
+            } else {
+                self.transplant(y, x);
+                self.nodes[y].right = self.right(z);
+                let right = self.right(y);
+                self.nodes[right].parent = y;
+            }
+
+            self.transplant(z, y);
+            self.nodes[y].left = self.left(z);
+            let left = self.left(y);
+            self.nodes[left].parent = y;
+            self.set_color(y, self.color(z));
+        }
+
+        if removed_color == Color::Black {
+            self.remove_fixup(x);
+        }
+
+        self.len -= 1;
+        self.free.push(z);
+        self.nodes[z].element.take().expect("linked nodes hold an element")
+    }
+
+    /// Verifies the red-black rules, the search-tree ordering, and the
+    /// parent links, describing the first violation found.
+    pub fn check_invariants(&self) -> Result<(), String>
+    where
+        T: Ord,
+    {
+        if self.color(self.root) != Color::Black {
+            return Err("root is red".to_string());
+        }
+
+        if self.root != NIL && self.parent(self.root) != NIL {
+            return Err("root has a parent".to_string());
+        }
+
+        let mut count = 0;
+        self.check_subtree(self.root, None, None, &mut count)?;
+
+        if count != self.len {
+            return Err(format!("tree holds {} nodes but len is {}", count, self.len));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the subtree rooted at `index`, whose elements must all lie
+    /// between `lower` and `upper`, returning its black height
+    fn check_subtree(
+        &self,
+        index: usize,
+        lower: Option<&T>,
+        upper: Option<&T>,
+        count: &mut usize,
+    ) -> Result<usize, String>
+    where
+        T: Ord,
+    {
+        if index == NIL {
+            return Ok(1);
+        }
+
+        *count += 1;
+        let (left, right) = (self.left(index), self.right(index));
+
+        for &child in &[left, right] {
+            if child != NIL && self.parent(child) != index {
+                return Err(format!("node {} has a stale parent link", child));
+            }
+
+            if self.color(index) == Color::Red && self.color(child) == Color::Red {
+                return Err(format!("red node {} has a red child", index));
+            }
+        }
+
+        let element = self.element(index);
+        if lower.is_some_and(|lower| element < lower) || upper.is_some_and(|upper| element > upper) {
+            return Err(format!("node {} is out of order", index));
+        }
+
+        let left_height = self.check_subtree(left, lower, Some(element), count)?;
+        let right_height = self.check_subtree(right, Some(element), upper, count)?;
+
+        if left_height != right_height {
+            return Err(format!("paths below node {} differ in black height", index));
+        }
+
+        Ok(left_height + (self.color(index) == Color::Black) as usize)
+    }
+}
+
+impl<T: Clone> RedBlackTree<T> {
+    pub fn inorder_walk(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len);
+        let mut unvisited = Vec::new();
+        let mut index = self.root;
+
+        while index != NIL || !unvisited.is_empty() {
+            while index != NIL {
+                unvisited.push(index);
+                index = self.left(index);
+            }
+
+            let node = unvisited.pop().expect("stack is non-empty");
+            result.push(self.element(node).clone());
+            index = self.right(node);
+        }
+
+        result
+    }
+}
+
+impl<T: Ord> RedBlackTree<T> {
+    pub fn insert(&mut self, element: T) {
+        let mut parent = NIL;
+        let mut index = self.root;
+
+        while index != NIL {
+            parent = index;
+            index = if element <= *self.element(index) {
+                self.left(index)
+            } else {
+                self.right(index)
+            };
+        }
+
+        let node = RbNode {
+            element: Some(element),
+            color: Color::Red,
+            parent,
+            left: NIL,
+            right: NIL,
+        };
+
+        let z = match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = node;
+                slot
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        };
+
+        if parent == NIL {
+            self.root = z;
+        } else if self.element(z) <= self.element(parent) {
+            self.nodes[parent].left = z;
+        } else {
+            self.nodes[parent].right = z;
+        }
+
+        self.len += 1;
+        self.insert_fixup(z);
+    }
+
+    // Return a reference to the value in the tree if it exists
+    pub fn search(&self, element: &T) -> Option<&T> {
+        match self.find(element) {
+            NIL => None,
+            index => Some(self.element(index)),
+        }
+    }
+
+    // Remove an element equal to the given one from the tree and return it
+    pub fn remove(&mut self, element: &T) -> Option<T> {
+        match self.find(element) {
+            NIL => None,
+            index => Some(self.remove_index(index)),
+        }
+    }
+
+    /// Index of the topmost node equal to `element`, or `NIL`
+    fn find(&self, element: &T) -> usize {
+        let mut index = self.root;
+
+        while index != NIL {
+            let current = self.element(index);
+
+            if *element == *current {
+                break;
+            } else if *element < *current {
+                index = self.left(index);
+            } else {
+                index = self.right(index);
+            }
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Deterministic pseudo-random sequence for shuffling test input
+    fn shuffled(n: u64) -> Vec<u64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut values: Vec<u64> = (0..n).collect();
+
+        for i in (1..values.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            values.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+
+        values
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = RedBlackTree::new();
+        assert_eq!(tree.check_invariants(), Ok(()));
+
+        for n in 0..200 {
+            tree.insert(n);
+            assert_eq!(tree.check_invariants(), Ok(()));
+        }
+        for n in shuffled(200) {
+            tree.insert(n);
+            assert_eq!(tree.check_invariants(), Ok(()));
+        }
+
+        assert_eq!(tree.len(), 400);
+        let expected: Vec<u64> = (0..200).flat_map(|n| vec![n, n]).collect();
+        assert_eq!(tree.inorder_walk(), expected);
+        assert_eq!(tree.search(&150), Some(&150));
+        assert_eq!(tree.search(&200), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = RedBlackTree::new();
+        for n in shuffled(300) {
+            tree.insert(n);
+        }
+
+        for n in shuffled(300).into_iter().filter(|n| n % 4 != 0) {
+            assert_eq!(tree.remove(&n), Some(n));
+            assert_eq!(tree.check_invariants(), Ok(()));
+        }
+
+        assert_eq!(tree.remove(&1), None);
+        assert_eq!(tree.len(), 75);
+        assert_eq!(tree.inorder_walk(), (0..300).filter(|n| n % 4 == 0).collect::<Vec<_>>());
+
+        // vacated slots are reused
+        for n in 0..10 {
+            tree.insert(n);
+        }
+        assert_eq!(tree.nodes.len(), 301);
+        assert_eq!(tree.check_invariants(), Ok(()));
+
+        for n in tree.inorder_walk() {
+            assert_eq!(tree.remove(&n), Some(n));
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_check_invariants_reports_violations() {
+        let mut tree = RedBlackTree::new();
+        for n in 0..10 {
+            tree.insert(n);
+        }
+
+        let root = tree.root;
+        tree.set_color(root, Color::Red);
+        assert_eq!(tree.check_invariants(), Err("root is red".to_string()));
+        tree.set_color(root, Color::Black);
+
+        let leftmost = tree.minimum(root);
+        let original = tree.color(leftmost);
+        let flipped = match original {
+            Color::Red => Color::Black,
+            Color::Black => Color::Red,
+        };
+        tree.set_color(leftmost, flipped);
+        assert!(tree.check_invariants().is_err());
+        tree.set_color(leftmost, original);
+
+        let right = tree.right(root);
+        tree.nodes[right].element = Some(0);
+        assert!(tree.check_invariants().unwrap_err().contains("out of order"));
+    }
+}
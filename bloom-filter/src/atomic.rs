@@ -0,0 +1,192 @@
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::hashing;
+use crate::{BloomFilter, SipBuildHasher};
+
+/// A Bloom filter that can be set and checked from many threads at once
+/// through a shared reference. Its bitmap is a vec of atomic words, and
+/// setting a bit is a single atomic or, so no locking is needed.
+///
+/// Bits are only ever set and never unset while the filter is shared,
+/// so relaxed atomics are enough: an item set on one thread is seen by
+/// `check` on any thread that synchronized with it afterwards, such as
+/// by joining it.
+pub struct AtomicBloomFilter<T, S = SipBuildHasher> {
+    bitmap: Vec<AtomicU64>,
+    bits: u64,
+    k: u32,
+    hashers: [S; 2],
+    _phantom: PhantomData<T>,
+}
+
+impl<T> AtomicBloomFilter<T> {
+    // Create a new atomic Bloom filter structure
+    // bitmap_cap is the size in bytes allocated for the internal bitmap
+    // n_items is the max number of items the Bloom Filter will hold
+    #[cfg(feature = "std")]
+    pub fn new(bitmap_cap: usize, n_items: usize) -> Self {
+        Self::new_with_hashers(bitmap_cap, n_items, [SipBuildHasher::new(), SipBuildHasher::new()])
+    }
+
+    // Create a new atomic Bloom filter structure, taking into account the
+    // desired rate of false positives between 0.0 and 1.0 exclusive
+    #[cfg(feature = "std")]
+    pub fn new_for_fp_rate(n_items: usize, fp_rate: f64) -> Self {
+        let bitmap_cap = BloomFilter::<T>::compute_bitmap_size(n_items, fp_rate);
+        Self::new(bitmap_cap, n_items)
+    }
+
+    // Return the keys used by the SipHasher
+    pub fn sip_keys(&self) -> [(u64, u64); 2] {
+        [self.hashers[0].keys(), self.hashers[1].keys()]
+    }
+}
+
+impl<T, S> AtomicBloomFilter<T, S> {
+    // Create a new atomic Bloom filter structure that hashes items with
+    // the given hashers, which should hash differently from each other
+    pub fn new_with_hashers(bitmap_cap: usize, n_items: usize, hashers: [S; 2]) -> Self {
+        assert!(bitmap_cap > 0 && n_items > 0);
+
+        let bits = (bitmap_cap as u64) * 8u64;
+        let k = BloomFilter::<T, S>::optimal_k(bits, n_items);
+        let bitmap = (0..bits.div_ceil(64)).map(|_| AtomicU64::new(0)).collect();
+
+        Self {
+            bitmap,
+            bits,
+            k,
+            hashers,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Return the number of bits in the filter
+    pub fn number_of_bits(&self) -> u64 {
+        self.bits
+    }
+
+    // Return the number of hash functions used for `check` and `set`
+    pub fn number_of_hashes(&self) -> u32 {
+        self.k
+    }
+
+    // Clear all the bits in the filter, removing all keys. Items set by
+    // other threads while the filter is being cleared may be partially
+    // cleared too, and should be set again
+    pub fn clear(&self) {
+        for word in &self.bitmap {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    // Split a bit offset into the index of its word and its mask in it
+    fn word_and_mask(offset: u64) -> (usize, u64) {
+        ((offset / 64) as usize, 1 << (offset % 64))
+    }
+}
+
+impl<T: Hash, S: BuildHasher> AtomicBloomFilter<T, S> {
+    // Add an item to the bloom filter
+    pub fn set(&self, item: &T) {
+        self.check_and_set(item);
+    }
+
+    // Check if an item exists in the bloom filter
+    // There can be false positives, but not false negatives
+    pub fn check(&self, item: &T) -> bool {
+        let mut hashes = [0u64, 0u64];
+
+        for k in 0..self.k {
+            let offset = hashing::bloom_hash(&self.hashers, &mut hashes, item, k) % self.bits;
+            let (word, mask) = Self::word_and_mask(offset);
+            if self.bitmap[word].load(Ordering::Relaxed) & mask == 0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Add an item to the bloom filter and return the previous state of
+    // this item. If two threads set the same new item at once, both may
+    // see it as new
+    pub fn check_and_set(&self, item: &T) -> bool {
+        let mut found = true;
+        let mut hashes = [0u64, 0u64];
+
+        for k in 0..self.k {
+            let offset = hashing::bloom_hash(&self.hashers, &mut hashes, item, k) % self.bits;
+            let (word, mask) = Self::word_and_mask(offset);
+            if self.bitmap[word].fetch_or(mask, Ordering::Relaxed) & mask == 0 {
+                found = false;
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixed_hashers;
+    #[cfg(feature = "std")]
+    use std::thread;
+
+    #[test]
+    fn test_set_check() {
+        let bloom = AtomicBloomFilter::new_with_hashers(10, 80, fixed_hashers());
+        assert_eq!(bloom.number_of_bits(), 80);
+        assert!(!bloom.check(&"apple"));
+
+        bloom.set(&"apple");
+        assert!(bloom.check(&"apple"));
+        assert!(bloom.check_and_set(&"apple"));
+        assert!(!bloom.check_and_set(&"banana"));
+
+        bloom.clear();
+        assert!(!bloom.check(&"apple"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_concurrent_set() {
+        let bloom = AtomicBloomFilter::new_for_fp_rate(40_000, 0.01);
+
+        thread::scope(|scope| {
+            for t in 0..4 {
+                let bloom = &bloom;
+                scope.spawn(move || {
+                    for item in (t * 10_000)..((t + 1) * 10_000) {
+                        bloom.set(&item);
+                    }
+                });
+            }
+        });
+
+        assert!((0..40_000).all(|item| bloom.check(&item)), "No false negatives");
+        let false_positives = (40_000..50_000).filter(|item| bloom.check(item)).count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_matches_bloom_filter() {
+        let keys = [(1, 2), (3, 4)];
+        let atomic = AtomicBloomFilter::new_with_hashers(8, 10, fixed_hashers());
+        let mut plain = BloomFilter::from_existing(&[0; 8], 64, atomic.number_of_hashes(), keys);
+
+        for item in 0..10 {
+            atomic.set(&item);
+            plain.set(&item);
+        }
+
+        let words: Vec<u64> = atomic.bitmap.iter().map(|word| word.load(Ordering::Relaxed)).collect();
+        let set_bits: Vec<u64> = (0..64).filter(|&bit| words[0] & (1 << bit) != 0).collect();
+        let plain_bits: Vec<u64> = (0..64).filter(|&bit| plain.bitmap.get(bit as usize).unwrap()).collect();
+        assert_eq!(set_bits, plain_bits, "Same hashers set the same bits");
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use crate::{BloomFilter, SipBuildHasher};
+
+// The number of 64-bit words in a block, which make up one cache line
+const BLOCK_WORDS: usize = 8;
+const BLOCK_BITS: u64 = BLOCK_WORDS as u64 * 64;
+
+type Block = [u64; BLOCK_WORDS];
+
+/// A Bloom filter split into blocks of one cache line each. The first
+/// hash of an item picks a block, and all of its k bits are set within
+/// that block, so a check costs at most one cache miss no matter how
+/// large k is. In exchange, the false positive rate is a little higher
+/// than a `BloomFilter` of the same size.
+pub struct BlockedBloomFilter<T, S = SipBuildHasher> {
+    blocks: Vec<Block>,
+    k: u32,
+    hashers: [S; 2],
+    _phantom: PhantomData<T>,
+}
+
+impl<T> BlockedBloomFilter<T> {
+    // Create a new blocked Bloom filter structure
+    // bitmap_cap is the size in bytes allocated for the internal bitmap,
+    // rounded up to a whole number of 64-byte blocks
+    // n_items is the max number of items the Bloom Filter will hold
+    #[cfg(feature = "std")]
+    pub fn new(bitmap_cap: usize, n_items: usize) -> Self {
+        Self::new_with_hashers(bitmap_cap, n_items, [SipBuildHasher::new(), SipBuildHasher::new()])
+    }
+
+    // Create a new blocked Bloom filter structure, taking into account
+    // the desired rate of false positives between 0.0 and 1.0 exclusive
+    #[cfg(feature = "std")]
+    pub fn new_for_fp_rate(n_items: usize, fp_rate: f64) -> Self {
+        let bitmap_cap = BloomFilter::<T>::compute_bitmap_size(n_items, fp_rate);
+        Self::new(bitmap_cap, n_items)
+    }
+
+    // Return the keys used by the SipHasher
+    pub fn sip_keys(&self) -> [(u64, u64); 2] {
+        [self.hashers[0].keys(), self.hashers[1].keys()]
+    }
+}
+
+impl<T, S> BlockedBloomFilter<T, S> {
+    // Create a new blocked Bloom filter structure that hashes items with
+    // the given hashers, which should hash differently from each other
+    pub fn new_with_hashers(bitmap_cap: usize, n_items: usize, hashers: [S; 2]) -> Self {
+        assert!(bitmap_cap > 0 && n_items > 0);
+
+        let n_blocks = bitmap_cap.div_ceil(BLOCK_WORDS * 8);
+        let k = BloomFilter::<T, S>::optimal_k(n_blocks as u64 * BLOCK_BITS, n_items);
+
+        Self {
+            blocks: vec![[0; BLOCK_WORDS]; n_blocks],
+            k,
+            hashers,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Return the number of bits in the filter
+    pub fn number_of_bits(&self) -> u64 {
+        self.blocks.len() as u64 * BLOCK_BITS
+    }
+
+    // Return the number of hash functions used for `check` and `set`
+    pub fn number_of_hashes(&self) -> u32 {
+        self.k
+    }
+
+    // Clear all the bits in the filter, removing all keys
+    pub fn clear(&mut self) {
+        for block in &mut self.blocks {
+            *block = [0; BLOCK_WORDS];
+        }
+    }
+}
+
+impl<T: Hash, S: BuildHasher> BlockedBloomFilter<T, S> {
+    // Add an item to the bloom filter
+    pub fn set(&mut self, item: &T) {
+        self.check_and_set(item);
+    }
+
+    // Check if an item exists in the bloom filter
+    // There can be false positives, but not false negatives
+    pub fn check(&self, item: &T) -> bool {
+        let (block, probes) = self.block_and_probes(item);
+        check_block(&self.blocks[block], probes)
+    }
+
+    // Add an item to the bloom filter and return the previous state of this item
+    pub fn check_and_set(&mut self, item: &T) -> bool {
+        let (block, probes) = self.block_and_probes(item);
+        check_and_set_block(&mut self.blocks[block], probes)
+    }
+
+    // Return the index of the item's block, and the word and mask of
+    // each of the k bits it maps to in that block. The bits are derived
+    // from the item's two hashes by double hashing
+    fn block_and_probes(&self, item: &T) -> (usize, impl Iterator<Item = (usize, u64)>) {
+        let first = self.hashers[0].hash_one(item);
+        let second = self.hashers[1].hash_one(item);
+
+        let block = (first % self.blocks.len() as u64) as usize;
+        // odd, so that the probes don't cycle before covering the block
+        let step = (first >> 32) | 1;
+        let probes = (0..self.k as u64).map(move |i| {
+            let bit = second.wrapping_add(i.wrapping_mul(step)) % BLOCK_BITS;
+            ((bit / 64) as usize, 1 << (bit % 64))
+        });
+
+        (block, probes)
+    }
+}
+
+// Check if all the probed bits of a block are set, one probe at a time,
+// stopping at the first unset bit. The `simd` feature doesn't change this
+fn check_block(block: &Block, mut probes: impl Iterator<Item = (usize, u64)>) -> bool {
+    probes.all(|(word, mask)| block[word] & mask != 0)
+}
+
+// Set all the probed bits of a block, and return whether they were all
+// already set
+#[cfg(not(feature = "simd"))]
+fn check_and_set_block(block: &mut Block, probes: impl Iterator<Item = (usize, u64)>) -> bool {
+    let mut found = true;
+
+    for (word, mask) in probes {
+        if block[word] & mask == 0 {
+            found = false;
+            block[word] |= mask;
+        }
+    }
+
+    found
+}
+
+// With the `simd` feature, the probes are first gathered into a mask of
+// the whole block, which is then set in the block word by word without
+// branching. Whether that loop is vectorized is up to the compiler
+#[cfg(feature = "simd")]
+fn check_and_set_block(block: &mut Block, probes: impl Iterator<Item = (usize, u64)>) -> bool {
+    let mut block_mask = [0; BLOCK_WORDS];
+    for (word, mask) in probes {
+        block_mask[word] |= mask;
+    }
+    let mut missing = 0;
+
+    for (word, mask) in block.iter_mut().zip(&block_mask) {
+        missing |= mask & !*word;
+        *word |= mask;
+    }
+
+    missing == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixed_hashers;
+
+    #[test]
+    fn test_set_check() {
+        let mut bloom = BlockedBloomFilter::new_with_hashers(100, 80, fixed_hashers());
+        assert_eq!(bloom.number_of_bits(), 1024, "Rounded up to whole blocks");
+        assert!(!bloom.check(&"apple"));
+
+        bloom.set(&"apple");
+        assert!(bloom.check(&"apple"));
+        assert!(bloom.check_and_set(&"apple"));
+        assert!(!bloom.check_and_set(&"banana"));
+
+        bloom.clear();
+        assert!(!bloom.check(&"apple"));
+    }
+
+    #[test]
+    fn test_fp_rate() {
+        let mut bloom = BlockedBloomFilter::new_with_hashers(
+            BloomFilter::<u32>::compute_bitmap_size(10_000, 0.01),
+            10_000,
+            fixed_hashers(),
+        );
+        for item in 0..10_000 {
+            bloom.set(&item);
+        }
+
+        assert!((0..10_000).all(|item| bloom.check(&item)), "No false negatives");
+        let false_positives = (10_000..20_000).filter(|item| bloom.check(item)).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_probes_stay_in_block() {
+        let bloom = BlockedBloomFilter::<u32>::new_with_hashers(4096, 500, fixed_hashers());
+        let (block, probes) = bloom.block_and_probes(&7);
+        assert!(block < 64);
+
+        let probes: Vec<_> = probes.collect();
+        assert_eq!(probes.len(), bloom.number_of_hashes() as usize);
+        assert!(probes.iter().all(|&(word, mask)| word < BLOCK_WORDS && mask.count_ones() == 1));
+    }
+
+    #[test]
+    fn test_block_probing() {
+        let mut block = [0; BLOCK_WORDS];
+        let probes = [(0, 1 << 3), (7, 1 << 63), (0, 1 << 3)];
+
+        assert!(!check_block(&block, probes.iter().copied()));
+        assert!(!check_and_set_block(&mut block, probes.iter().copied()));
+        assert_eq!(block, [1 << 3, 0, 0, 0, 0, 0, 0, 1 << 63]);
+
+        assert!(check_block(&block, probes.iter().copied()));
+        assert!(check_and_set_block(&mut block, probes[1..].iter().copied()));
+        assert!(!check_block(&block, [(0, 1 << 3), (4, 1)].iter().copied()));
+    }
+}
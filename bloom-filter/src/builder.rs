@@ -0,0 +1,247 @@
+use core::f64;
+use core::fmt;
+
+use crate::{ln, BloomFilter, SipBuildHasher};
+
+/// Builds a `BloomFilter` from exactly two of its bitmap size in bytes,
+/// the number of items it will hold and its desired false positive rate.
+/// The third is derived from the other two, and the number of hashes is
+/// the optimal one for the size and number of items.
+///
+/// `BloomFilter::new` and `BloomFilter::new_for_fp_rate` are shorthands
+/// for building with random sip keys from a size or an fp rate, which
+/// panic instead of returning a `BuildError`.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use bloom_filter::BloomFilterBuilder;
+///
+/// let mut bloom = BloomFilterBuilder::new()
+///     .expected_items(1000)
+///     .fp_rate(0.01)
+///     .build()
+///     .unwrap();
+/// bloom.set(&"apple");
+/// assert!(bloom.check(&"apple"));
+/// # }
+/// ```
+pub struct BloomFilterBuilder<S = SipBuildHasher> {
+    bitmap_cap: Option<usize>,
+    n_items: Option<usize>,
+    fp_rate: Option<f64>,
+    hashers: [S; 2],
+}
+
+// The reasons `BloomFilterBuilder::build` can reject its parameters
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    // Fewer than two of the size, the number of items and the false
+    // positive rate were given
+    Underspecified,
+    // All three were given, so they could contradict each other
+    Overspecified,
+    // The size or the number of items is zero
+    EmptyFilter,
+    // The false positive rate isn't between 0.0 and 1.0 exclusive
+    InvalidFpRate(f64),
+    // The bitmap is too small to hold a single item at the false
+    // positive rate
+    TooSmall,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Underspecified => {
+                write!(f, "two of the bitmap size, expected items and fp rate are needed")
+            }
+            BuildError::Overspecified => {
+                write!(f, "only two of the bitmap size, expected items and fp rate can be given")
+            }
+            BuildError::EmptyFilter => write!(f, "bloom filter has no bits or no items"),
+            BuildError::InvalidFpRate(fp_rate) => {
+                write!(f, "false positive rate {} is not between 0 and 1", fp_rate)
+            }
+            BuildError::TooSmall => write!(f, "bloom filter is too small for its false positive rate"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+impl BloomFilterBuilder {
+    // Start building a filter that hashes with random sip keys
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::with_hashers([SipBuildHasher::new(), SipBuildHasher::new()])
+    }
+
+    // Hash with the given sip keys instead, such as the ones of an
+    // existing filter
+    pub fn sip_keys(self, sip_keys: [(u64, u64); 2]) -> Self {
+        self.hashers([
+            SipBuildHasher::with_keys(sip_keys[0].0, sip_keys[0].1),
+            SipBuildHasher::with_keys(sip_keys[1].0, sip_keys[1].1),
+        ])
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for BloomFilterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> BloomFilterBuilder<S> {
+    // Start building a filter that hashes items with the given hashers,
+    // which should hash differently from each other
+    pub fn with_hashers(hashers: [S; 2]) -> Self {
+        Self {
+            bitmap_cap: None,
+            n_items: None,
+            fp_rate: None,
+            hashers,
+        }
+    }
+
+    // Hash with the given hashers instead
+    pub fn hashers<H>(self, hashers: [H; 2]) -> BloomFilterBuilder<H> {
+        BloomFilterBuilder {
+            bitmap_cap: self.bitmap_cap,
+            n_items: self.n_items,
+            fp_rate: self.fp_rate,
+            hashers,
+        }
+    }
+
+    // Set the size in bytes of the bitmap
+    pub fn bitmap_size(mut self, bitmap_cap: usize) -> Self {
+        self.bitmap_cap = Some(bitmap_cap);
+        self
+    }
+
+    // Set the max number of items the filter will hold
+    pub fn expected_items(mut self, n_items: usize) -> Self {
+        self.n_items = Some(n_items);
+        self
+    }
+
+    // Set the desired rate of false positives, between 0.0 and 1.0
+    // exclusive, once the filter holds the expected number of items
+    pub fn fp_rate(mut self, fp_rate: f64) -> Self {
+        self.fp_rate = Some(fp_rate);
+        self
+    }
+
+    // Check the parameters and create the filter
+    pub fn build<T>(self) -> Result<BloomFilter<T, S>, BuildError> {
+        if let Some(fp_rate) = self.fp_rate {
+            if !(fp_rate > 0.0 && fp_rate < 1.0) {
+                return Err(BuildError::InvalidFpRate(fp_rate));
+            }
+        }
+        if self.bitmap_cap == Some(0) || self.n_items == Some(0) {
+            return Err(BuildError::EmptyFilter);
+        }
+
+        let (bitmap_cap, n_items) = match (self.bitmap_cap, self.n_items, self.fp_rate) {
+            (Some(_), Some(_), Some(_)) => return Err(BuildError::Overspecified),
+            (Some(bitmap_cap), Some(n_items), None) => (bitmap_cap, n_items),
+            (None, Some(n_items), Some(fp_rate)) => {
+                let bitmap_cap = BloomFilter::<T, S>::compute_bitmap_size(n_items, fp_rate);
+                (bitmap_cap, n_items)
+            }
+            (Some(bitmap_cap), None, Some(fp_rate)) => {
+                // The inverse of `compute_bitmap_size`
+                let log2_squared = f64::consts::LN_2 * f64::consts::LN_2;
+                let n_items = (bitmap_cap as f64 * -8.0 * log2_squared / ln(fp_rate)) as usize;
+                if n_items == 0 {
+                    return Err(BuildError::TooSmall);
+                }
+                (bitmap_cap, n_items)
+            }
+            _ => return Err(BuildError::Underspecified),
+        };
+
+        Ok(BloomFilter::new_with_hashers(bitmap_cap, n_items, self.hashers))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixed_hashers;
+
+    fn builder() -> BloomFilterBuilder {
+        BloomFilterBuilder::with_hashers(fixed_hashers())
+    }
+
+    #[test]
+    fn test_any_two_parameters() {
+        let by_size = builder().bitmap_size(10).expected_items(80);
+        let bloom = by_size.build::<u32>().unwrap();
+        assert_eq!(bloom.number_of_bits(), 80);
+        assert_eq!(bloom.number_of_hashes(), 1);
+
+        let by_rate = builder().expected_items(1000).fp_rate(0.01);
+        let bloom = by_rate.build::<u32>().unwrap();
+        assert_eq!(bloom.number_of_bits(), 8 * BloomFilter::<u32>::compute_bitmap_size(1000, 0.01) as u64);
+
+        let by_size_and_rate = builder().bitmap_size(1199).fp_rate(0.01);
+        let derived = by_size_and_rate.build::<u32>().unwrap();
+        assert_eq!(derived.number_of_bits(), bloom.number_of_bits());
+        assert_eq!(derived.number_of_hashes(), bloom.number_of_hashes());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_constructor_shorthands() {
+        let bloom = BloomFilter::<u32>::new(10, 80);
+        assert_eq!(bloom.number_of_bits(), 80);
+        assert_eq!(bloom.number_of_hashes(), 1);
+
+        let by_rate = builder().expected_items(1000).fp_rate(0.01).build::<u32>().unwrap();
+        let bloom = BloomFilter::<u32>::new_for_fp_rate(1000, 0.01);
+        assert_eq!(bloom.number_of_bits(), by_rate.number_of_bits());
+        assert_eq!(bloom.number_of_hashes(), by_rate.number_of_hashes());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "false positive rate 1 is not between 0 and 1")]
+    fn test_constructor_shorthand_panics() {
+        BloomFilter::<u32>::new_for_fp_rate(1000, 1.0);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let build = |builder: BloomFilterBuilder| builder.build::<u32>().err();
+
+        assert_eq!(build(builder().fp_rate(0.1)), Some(BuildError::Underspecified));
+        let all_three = builder().bitmap_size(10).expected_items(10).fp_rate(0.1);
+        assert_eq!(build(all_three), Some(BuildError::Overspecified));
+        assert_eq!(build(builder().bitmap_size(0).expected_items(10)), Some(BuildError::EmptyFilter));
+        assert_eq!(build(builder().expected_items(10).fp_rate(1.0)), Some(BuildError::InvalidFpRate(1.0)));
+        assert_eq!(build(builder().bitmap_size(1).fp_rate(1e-9)), Some(BuildError::TooSmall));
+    }
+
+    #[test]
+    fn test_hashers() {
+        let keys = [(5, 6), (7, 8)];
+        let bloom = builder().sip_keys(keys).bitmap_size(8).expected_items(10);
+        assert_eq!(bloom.build::<u32>().unwrap().sip_keys(), keys);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_other_hashers() {
+        use std::collections::hash_map::RandomState;
+
+        let hashers = [RandomState::new(), RandomState::new()];
+        let mut bloom = BloomFilterBuilder::with_hashers(hashers).expected_items(10).fp_rate(0.1).build().unwrap();
+        bloom.set(&"apple");
+        assert!(bloom.check(&"apple"));
+    }
+}
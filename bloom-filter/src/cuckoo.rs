@@ -0,0 +1,280 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::SipBuildHasher;
+
+// The number of fingerprints that fit in a bucket
+const BUCKET_SIZE: usize = 4;
+// How many fingerprints an insert may kick out of their buckets before
+// the filter is considered full
+const MAX_KICKS: usize = 500;
+// A fingerprint is never zero, so zero marks an empty slot
+const EMPTY: u16 = 0;
+
+type Bucket = [u16; BUCKET_SIZE];
+
+/// A filter that stores a short fingerprint of every item in one of two
+/// buckets, moving fingerprints between their two buckets to make room
+/// like a cuckoo hash table. Unlike a Bloom filter, items can be removed
+/// again, and at false positive rates below about 3% it takes less space.
+pub struct CuckooFilter<T, S = SipBuildHasher> {
+    buckets: Vec<Bucket>,
+    len: usize,
+    // A fingerprint that was left without a slot when the filter filled
+    // up, along with one of its two bucket indices
+    victim: Option<(usize, u16)>,
+    hasher: S,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> CuckooFilter<T> {
+    // Create a new cuckoo filter sized for `capacity` items, as
+    // `new_with_hasher` describes
+    #[cfg(feature = "std")]
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_hasher(capacity, SipBuildHasher::new())
+    }
+}
+
+impl<T, S> CuckooFilter<T, S> {
+    // Create a new cuckoo filter sized for `capacity` items, which hashes
+    // items with the given hasher. Inserts usually start failing once the
+    // filter is about 95% full, so it gets slots for at least
+    // `capacity / 0.95` fingerprints. Filters with fewer than a couple of
+    // thousand slots can still fill up a little before `capacity` items
+    pub fn new_with_hasher(capacity: usize, hasher: S) -> Self {
+        assert!(capacity > 0);
+
+        // the number of buckets is a power of two, so that xor-ing a
+        // bucket index with any hash gives another valid index
+        let slots = capacity + capacity.div_ceil(19);
+        let n_buckets = slots.div_ceil(BUCKET_SIZE).next_power_of_two();
+
+        Self {
+            buckets: vec![[EMPTY; BUCKET_SIZE]; n_buckets],
+            len: 0,
+            victim: None,
+            hasher,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Return the number of items in the filter
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Return the number of fingerprints the filter has slots for. Inserts
+    // usually start failing once it is about 95% full
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    // Clear all the buckets in the filter, removing all items
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            *bucket = [EMPTY; BUCKET_SIZE];
+        }
+        self.len = 0;
+        self.victim = None;
+    }
+
+    // Put the fingerprint in an empty slot of the bucket, if it has one
+    fn add_to_bucket(&mut self, index: usize, fingerprint: u16) -> bool {
+        match self.buckets[index].iter_mut().find(|slot| **slot == EMPTY) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Clear one slot of the bucket holding the fingerprint, if it has one
+    fn remove_from_bucket(&mut self, index: usize, fingerprint: u16) -> bool {
+        match self.buckets[index].iter_mut().find(|slot| **slot == fingerprint) {
+            Some(slot) => {
+                *slot = EMPTY;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Hash, S: BuildHasher> CuckooFilter<T, S> {
+    // Add an item to the filter. Returns false if the filter is too full
+    // to take it, in which case nothing changes
+    pub fn insert(&mut self, item: &T) -> bool {
+        self.place_victim();
+        if self.victim.is_some() {
+            return false;
+        }
+
+        let (mut fingerprint, first) = self.fingerprint_and_index(item);
+        let second = self.alt_index(first, fingerprint);
+        if self.add_to_bucket(first, fingerprint) || self.add_to_bucket(second, fingerprint) {
+            self.len += 1;
+            return true;
+        }
+
+        // both buckets are full, so evict fingerprints to their other
+        // buckets until one of them lands in a bucket with room
+        let mut index = second;
+        for kick in 0..MAX_KICKS {
+            let slot = (fingerprint as usize + kick) % BUCKET_SIZE;
+            mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+
+            index = self.alt_index(index, fingerprint);
+            if self.add_to_bucket(index, fingerprint) {
+                self.len += 1;
+                return true;
+            }
+        }
+
+        // the item itself made it into a bucket, but the last evicted
+        // fingerprint has nowhere to go, so it is kept aside
+        self.victim = Some((index, fingerprint));
+        self.len += 1;
+
+        true
+    }
+
+    // Check if an item exists in the filter
+    // There can be false positives, but not false negatives
+    pub fn contains(&self, item: &T) -> bool {
+        let (fingerprint, first) = self.fingerprint_and_index(item);
+        let second = self.alt_index(first, fingerprint);
+
+        self.buckets[first].contains(&fingerprint)
+            || self.buckets[second].contains(&fingerprint)
+            || self.victim.is_some_and(|(index, victim)| {
+                victim == fingerprint && (index == first || index == second)
+            })
+    }
+
+    // Remove an item from the filter and return whether it was found.
+    // Only remove items that were inserted: removing a false positive
+    // removes another item's fingerprint instead
+    pub fn remove(&mut self, item: &T) -> bool {
+        let (fingerprint, first) = self.fingerprint_and_index(item);
+        let second = self.alt_index(first, fingerprint);
+
+        if self.victim.is_some_and(|(index, victim)| {
+            victim == fingerprint && (index == first || index == second)
+        }) {
+            self.victim = None;
+        } else if self.remove_from_bucket(first, fingerprint)
+            || self.remove_from_bucket(second, fingerprint)
+        {
+            self.place_victim();
+        } else {
+            return false;
+        }
+
+        self.len -= 1;
+        true
+    }
+
+    // Move the fingerprint that was kept aside into one of its buckets,
+    // if either of them has room now
+    fn place_victim(&mut self) {
+        if let Some((index, fingerprint)) = self.victim {
+            let alt = self.alt_index(index, fingerprint);
+            if self.add_to_bucket(index, fingerprint) || self.add_to_bucket(alt, fingerprint) {
+                self.victim = None;
+            }
+        }
+    }
+
+    // Split the hash of an item into its fingerprint and its first
+    // bucket index
+    fn fingerprint_and_index(&self, item: &T) -> (u16, usize) {
+        let hash = self.hasher.hash_one(item);
+        let fingerprint = nonzero_fingerprint((hash >> 48) as u16);
+        let index = hash as usize & (self.buckets.len() - 1);
+
+        (fingerprint, index)
+    }
+
+    // Return the other bucket the fingerprint can go in. Since it only
+    // depends on the fingerprint, fingerprints can be moved between
+    // their two buckets without knowing the items they came from
+    fn alt_index(&self, index: usize, fingerprint: u16) -> usize {
+        (index ^ self.hasher.hash_one(fingerprint) as usize) & (self.buckets.len() - 1)
+    }
+}
+
+// Map the one fingerprint value that marks empty slots to another one
+fn nonzero_fingerprint(fingerprint: u16) -> u16 {
+    if fingerprint == EMPTY {
+        1
+    } else {
+        fingerprint
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_contains() {
+        let mut filter = CuckooFilter::new_with_hasher(1000, SipBuildHasher::with_keys(1, 2));
+        assert!(filter.is_empty());
+        assert_eq!(filter.capacity(), 2048, "Room for 1000 / 0.95 rounded up");
+
+        for item in 0..1000 {
+            assert!(filter.insert(&item));
+        }
+        assert_eq!(filter.len(), 1000);
+        assert!((0..1000).all(|item| filter.contains(&item)), "No false negatives");
+
+        let false_positives = (1000..11_000).filter(|item| filter.contains(item)).count();
+        assert!(false_positives < 50, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut filter = CuckooFilter::new_with_hasher(100, SipBuildHasher::with_keys(1, 2));
+        for item in 0..50 {
+            filter.insert(&item);
+        }
+
+        for item in (0..50).step_by(2) {
+            assert!(filter.remove(&item));
+        }
+        assert_eq!(filter.len(), 25);
+        assert!((1..50).step_by(2).all(|item| filter.contains(&item)));
+        assert_eq!((0..50).step_by(2).filter(|item| filter.contains(item)).count(), 0);
+        assert!(!filter.remove(&1000));
+    }
+
+    #[test]
+    fn test_full() {
+        // fixed keys, so the filter fills up the same way every run
+        let mut filter = CuckooFilter::new_with_hasher(64, SipBuildHasher::with_keys(1, 2));
+        let inserted = (0..1000).take_while(|item| filter.insert(item)).count();
+        assert!(inserted >= 64, "Only {} items fit", inserted);
+        assert!(inserted <= filter.capacity());
+        assert!((0..inserted).all(|item| filter.contains(&item)), "Items kept when full");
+
+        assert!(!filter.insert(&2000));
+        for item in 0..inserted / 2 {
+            assert!(filter.remove(&item));
+        }
+        assert!(filter.insert(&2000), "Removing makes room again");
+        assert!((inserted / 2..inserted).all(|item| filter.contains(&item)));
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&1));
+    }
+}
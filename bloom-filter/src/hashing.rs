@@ -0,0 +1,60 @@
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use rand::Rng;
+use siphasher::sip::SipHasher13;
+
+/// The default hasher of the filters in this crate: SipHash 1-3 with
+/// random keys. Unlike other `BuildHasher`s, its keys can be read back,
+/// which lets a filter be saved and restored.
+#[derive(Debug, Clone)]
+pub struct SipBuildHasher {
+    k0: u64,
+    k1: u64,
+}
+
+impl SipBuildHasher {
+    // Create a builder with random keys
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        let mut rng = rand::thread_rng();
+        Self::with_keys(rng.gen(), rng.gen())
+    }
+
+    pub fn with_keys(k0: u64, k1: u64) -> Self {
+        Self { k0, k1 }
+    }
+
+    // Return the keys given to every hasher this builds
+    pub fn keys(&self) -> (u64, u64) {
+        (self.k0, self.k1)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SipBuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for SipBuildHasher {
+    type Hasher = SipHasher13;
+
+    fn build_hasher(&self) -> SipHasher13 {
+        SipHasher13::new_with_keys(self.k0, self.k1)
+    }
+}
+
+// Return the k-th hash of an item for a Bloom filter. The first two are
+// computed with the two hashers and stored in `hashes`, and the rest are
+// derived from those, so `k` must count up from zero
+pub(crate) fn bloom_hash<T: Hash, S: BuildHasher>(hashers: &[S; 2], hashes: &mut [u64; 2], item: &T, k: u32) -> u64 {
+    if k < 2 {
+        let hash = hashers[k as usize].hash_one(item);
+        hashes[k as usize] = hash;
+
+        hash
+    } else {
+        hashes[0].wrapping_add((k as u64).wrapping_mul(hashes[1]) % 0xffffffffffffffc5)
+    }
+}
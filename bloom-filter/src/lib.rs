@@ -1,63 +1,252 @@
-#![allow(dead_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use bit_vec::BitVec;
-use siphasher::sip::SipHasher13;
-use std::marker::PhantomData;
-use std::hash::{Hash, Hasher};
-
-use std::cmp;
-use std::f64;
-use rand::Rand;
+extern crate alloc;
 
-pub struct BloomFilter<T> {
-    bitmap: BitVec,
+use alloc::vec::Vec;
+use bit_vec::BitVec;
+use core::marker::PhantomData;
+use core::hash::{BuildHasher, Hash};
+
+use core::borrow::Borrow;
+use core::cmp;
+use core::f64;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(test)]
+use alloc::vec;
+#[cfg(test)]
+use rand::Rng;
+
+#[cfg(target_has_atomic = "64")]
+mod atomic;
+mod blocked;
+mod builder;
+mod cuckoo;
+mod hashing;
+#[cfg(feature = "std")]
+mod scalable;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod storage;
+
+#[cfg(target_has_atomic = "64")]
+pub use atomic::AtomicBloomFilter;
+pub use blocked::BlockedBloomFilter;
+pub use builder::{BloomFilterBuilder, BuildError};
+pub use cuckoo::CuckooFilter;
+pub use hashing::SipBuildHasher;
+#[cfg(feature = "std")]
+pub use scalable::ScalableBloomFilter;
+pub use storage::BitmapStorage;
+
+// A filter is generic over the `BuildHasher` used to hash its items,
+// so any hasher can be plugged in. Only the default one can be saved
+// and restored, since other hashers don't expose their keys. It is also
+// generic over where its bits live, see `BitmapStorage`
+pub struct BloomFilter<T, S = SipBuildHasher, B = BitVec> {
+    bitmap: B,
     bits: u64,
     k: u32,
-    sips: [SipHasher13; 2],
-    _phantomData: PhantomData<T>,
+    // Every item is hashed once by each of these, and the k offsets
+    // are derived from the two hashes
+    hashers: [S; 2],
+    _phantom: PhantomData<T>,
 }
 
+// The number of items the batch methods hash before touching the bitmap
+const BATCH_SIZE: usize = 64;
+
+// The first bytes of a filter written by `write_to`
+#[cfg(feature = "std")]
+const MAGIC: [u8; 4] = *b"BLMF";
+// The version of the layout written by `to_bytes`
+const BYTES_VERSION: u8 = 1;
+// The version byte, the bit count, k and the four sip key halves
+const BYTES_HEADER_LEN: usize = 1 + 8 + 4 + 4 * 8;
+
+// The reasons `BloomFilter::from_bytes` can reject a blob
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromBytesError {
+    // The blob was written by an unknown version of `to_bytes`
+    UnsupportedVersion(u8),
+    // The blob is too short for its header, or its bitmap doesn't match
+    // the bit count
+    InvalidLength,
+    // The bit count or the number of hashes is zero
+    EmptyFilter,
+}
+
+impl fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromBytesError::UnsupportedVersion(version) => {
+                write!(f, "unsupported bloom filter version {}", version)
+            }
+            FromBytesError::InvalidLength => write!(f, "bloom filter bitmap has the wrong length"),
+            FromBytesError::EmptyFilter => write!(f, "bloom filter has no bits or no hashes"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromBytesError {}
+
+// The reasons `BloomFilter::try_merge` can refuse to merge two filters.
+// Each carries the values of this filter and of the other one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    // The filters have a different number of bits
+    BitsMismatch(u64, u64),
+    // The filters use a different number of hashes
+    HashesMismatch(u32, u32),
+    // The filters hash items with different sip keys
+    KeysMismatch([(u64, u64); 2], [(u64, u64); 2]),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::BitsMismatch(ours, theirs) => {
+                write!(f, "cannot merge a bloom filter of {} bits with one of {} bits", ours, theirs)
+            }
+            MergeError::HashesMismatch(ours, theirs) => {
+                write!(f, "cannot merge a bloom filter of {} hashes with one of {} hashes", ours, theirs)
+            }
+            MergeError::KeysMismatch(..) => write!(f, "cannot merge bloom filters with different sip keys"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeError {}
+
 impl<T> BloomFilter<T> {
     // Create a new Bloom Filter structure
     // bitmap_cap is the size in bytes allocated for the internal bitmap
     // n_items is the max number of items the Bloom Filter will hold
-    pub fn new(bitmap_cap: usize, n_items: usize) -> Size {
-        assert!(bitmap_cap > 0 && n_items > 0);
-
-        let bits = (bitmap_cap as u64) * 8u64;
-        let k = Self::calculate_k(bits, n_items);
-        let bitmap = BitVec::from_elem(bits as usize, false);
-        let sips = [Self::sip_new(), Self::sip_new()];
-
-        Self {
-            bitmap,
-            bits,
-            k,
-            sips,
-            _phantomData: PhantomData,
-        }
+    // This is shorthand for building it with `BloomFilterBuilder`, and
+    // panics on the parameters the builder would reject
+    #[cfg(feature = "std")]
+    pub fn new(bitmap_cap: usize, n_items: usize) -> Self {
+        let builder = BloomFilterBuilder::new().bitmap_size(bitmap_cap).expected_items(n_items);
+        builder.build().unwrap_or_else(|err| panic!("{}", err))
     }
     
     // Create a new Bloom Filter structure, taking into account the
     // desired rate of false positives between 0.0 and 1.0 exclusive
+    // Like `new`, this is shorthand for `BloomFilterBuilder`
+    #[cfg(feature = "std")]
     pub fn new_for_fp_rate(n_items: usize, fp_rate: f64) -> Self {
-        let bitmap_cap = Self::compute_bitmap_cap(n_items, fp_rate);
-        Self::new(bitmap_cap, n_items)
+        let builder = BloomFilterBuilder::new().expected_items(n_items).fp_rate(fp_rate);
+        builder.build().unwrap_or_else(|err| panic!("{}", err))
     }
 
     // Create a bloom filter structure with an existing state.
     // The state is assumed to be retrieved from an existing bloom filter
     pub fn from_existing(bitmap: &[u8], bits: u64, k: u32, sip_keys: [(u64, u64); 2]) -> Self {
-        let sips = [SipHasher13::new_with_keys(sip_keys[0].0, sip_keys[0].1), SipHasher13::new_with_keys(sip_keys[0].0, sip_keys[0].1)];
+        let hashers = [
+            SipBuildHasher::with_keys(sip_keys[0].0, sip_keys[0].1),
+            SipBuildHasher::with_keys(sip_keys[1].0, sip_keys[1].1),
+        ];
         Self {
             bitmap: BitVec::from_bytes(bitmap),
             bits,
             k,
-            sips,
-            _phantomData: PhantomData,
+            hashers,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Create a bloom filter structure from a blob returned by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesError> {
+        if bytes.len() < BYTES_HEADER_LEN {
+            return Err(FromBytesError::InvalidLength);
+        }
+        if bytes[0] != BYTES_VERSION {
+            return Err(FromBytesError::UnsupportedVersion(bytes[0]));
+        }
+
+        let (header, bitmap) = bytes.split_at(BYTES_HEADER_LEN);
+        let u64_at = |pos: usize| {
+            let mut word = [0; 8];
+            word.copy_from_slice(&header[pos..pos + 8]);
+            u64::from_le_bytes(word)
+        };
+
+        let bits = u64_at(1);
+        let mut k = [0; 4];
+        k.copy_from_slice(&header[9..13]);
+        let k = u32::from_le_bytes(k);
+        let sip_keys = [(u64_at(13), u64_at(21)), (u64_at(29), u64_at(37))];
+
+        if bits == 0 || k == 0 {
+            return Err(FromBytesError::EmptyFilter);
+        }
+        if bitmap.len() as u64 != bits.div_ceil(8) {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        Ok(Self::from_existing(bitmap, bits, k, sip_keys))
+    }
+
+    // Read a filter written by `write_to`. Fails with `InvalidData` if
+    // the reader doesn't hold a filter, wrapping a `FromBytesError` when
+    // the filter itself is invalid
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a bloom filter"));
+        }
+
+        let mut bytes = vec![0; BYTES_HEADER_LEN];
+        reader.read_exact(&mut bytes)?;
+        if bytes[0] != BYTES_VERSION {
+            let err = FromBytesError::UnsupportedVersion(bytes[0]);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+
+        // The bit count isn't trusted until `from_bytes` checks it, so
+        // the bitmap is read without allocating all of it up front
+        let mut bits = [0; 8];
+        bits.copy_from_slice(&bytes[1..9]);
+        let bitmap_len = u64::from_le_bytes(bits).div_ceil(8);
+        reader.take(bitmap_len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != BYTES_HEADER_LEN as u64 + bitmap_len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Self::from_bytes(&bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl<T, S> BloomFilter<T, S> {
+    // Create a new Bloom Filter structure that hashes items with the
+    // given hashers, which should hash differently from each other
+    pub fn new_with_hashers(bitmap_cap: usize, n_items: usize, hashers: [S; 2]) -> Self {
+        assert!(bitmap_cap > 0 && n_items > 0);
+
+        let bits = (bitmap_cap as u64) * 8u64;
+        let k = Self::optimal_k(bits, n_items);
+        let bitmap = BitVec::from_elem(bits as usize, false);
+
+        Self {
+            bitmap,
+            bits,
+            k,
+            hashers,
+            _phantom: PhantomData,
         }
     }
 
+    // Like `new_for_fp_rate`, but hashing items with the given hashers
+    pub fn new_for_fp_rate_with_hashers(n_items: usize, fp_rate: f64, hashers: [S; 2]) -> Self {
+        let builder = BloomFilterBuilder::with_hashers(hashers).expected_items(n_items).fp_rate(fp_rate);
+        builder.build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
     // Compute a recommended bitmap size for the given `n_items` and `fp_rate`
     // `fp_rate` must be between 0.0 and 1.0 exclusive 
     pub fn compute_bitmap_size(n_items: usize, fp_rate: f64) -> usize {
@@ -67,7 +256,92 @@ impl<T> BloomFilter<T> {
         let log2 = f64::consts::LN_2;
         let log2_squared = log2 * log2;
 
-        ((n_items as f64) * f64::ln(fp_rate) / (-8.0 * log2_squared)).ceil() as usize
+        ceil((n_items as f64) * ln(fp_rate) / (-8.0 * log2_squared)) as usize
+    }
+
+    // Compute the number of hash functions that gives the lowest false
+    // positive rate for a filter of `bits` bits holding `n_items` items
+    pub fn optimal_k(bits: u64, n_items: usize) -> u32 {
+        let m = bits as f64;
+        let n = n_items as f64;
+        let k = ceil(m / n * f64::consts::LN_2) as u32;
+        
+        cmp::max(k, 1)
+    }
+}
+
+impl<T, B: BitmapStorage> BloomFilter<T, SipBuildHasher, B> {
+    // Return the keys used by the SipHasher
+    pub fn sip_keys(&self) -> [(u64, u64); 2] {
+        [self.hashers[0].keys(), self.hashers[1].keys()]
+    }
+
+    // Return the whole state of the filter as one blob: a version byte,
+    // then the number of bits, the number of hashes and the sip keys in
+    // little-endian order, then the bitmap
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bitmap = self.bitmap();
+        let mut bytes = Vec::with_capacity(BYTES_HEADER_LEN + bitmap.len());
+
+        bytes.push(BYTES_VERSION);
+        bytes.extend_from_slice(&self.bits.to_le_bytes());
+        bytes.extend_from_slice(&self.k.to_le_bytes());
+        for (k0, k1) in &self.sip_keys() {
+            bytes.extend_from_slice(&k0.to_le_bytes());
+            bytes.extend_from_slice(&k1.to_le_bytes());
+        }
+        bytes.extend_from_slice(&bitmap);
+
+        bytes
+    }
+
+    // Write the filter in a portable format: four magic bytes, then the
+    // blob returned by `to_bytes`. Every number is little-endian and the
+    // bitmap is in byte order, so a filter written on one architecture
+    // reads back the same on any other with `read_from`
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&self.to_bytes())
+    }
+
+    // Add all the items of `other` to this filter, as if they had been
+    // set on it. Both filters must have been created with the same number
+    // of bits, number of hashes and sip keys, such as partial filters
+    // built by different workers from the same parameters
+    pub fn try_merge(&mut self, other: &Self) -> Result<(), MergeError> {
+        let (ours, theirs) = (self.bitmap.number_of_bits(), other.bitmap.number_of_bits());
+        if self.bits != other.bits || ours != theirs {
+            return Err(MergeError::BitsMismatch(self.bits, other.bits));
+        }
+        if self.k != other.k {
+            return Err(MergeError::HashesMismatch(self.k, other.k));
+        }
+        if self.sip_keys() != other.sip_keys() {
+            return Err(MergeError::KeysMismatch(self.sip_keys(), other.sip_keys()));
+        }
+
+        self.bitmap.union_with(&other.bitmap);
+        Ok(())
+    }
+}
+
+impl<T, S, B: BitmapStorage> BloomFilter<T, S, B> {
+    // Create a Bloom Filter structure that sets and checks bits in the
+    // given storage, without copying it. Any bits already set in it are
+    // kept, so a filter saved to a file can be mapped back into memory
+    // and used as is, as long as it is given the same k and hashers
+    pub fn from_storage(bitmap: B, k: u32, hashers: [S; 2]) -> Self {
+        let bits = bitmap.number_of_bits() as u64;
+        assert!(bits > 0 && k > 0);
+
+        Self {
+            bitmap,
+            bits,
+            k,
+            hashers,
+            _phantom: PhantomData,
+        }
     }
 
     // Return the bitmap as a vector of bytes
@@ -77,7 +351,7 @@ impl<T> BloomFilter<T> {
 
     // Return the number of bits in the filter 
     pub fn number_of_bits(&self) -> u64 {
-        self.bitmap_bits
+        self.bits
     }
 
     // Return the number of hash functions used for `check` and `set`
@@ -85,38 +359,20 @@ impl<T> BloomFilter<T> {
         self.k
     }
 
-    // Return the keys used by the SipHasher
-    pub fn sip_keys(&self) -> [(u64, u64); 2] {
-        [self.sips[0].keys(), self.sips[1].keys()]
-    }
-
-    fn optimal_k(bits: u64, n_items: usize) -> u32 {
-        let m = bits as f64;
-        let n = n_items as f64;
-        let k = (m / n * f64::ln(2.0f64)).ceil() as u32;
-        
-        cmp::max(k, 1)
-    }
-
     // Clear all the bits in the filter, removing all keys
     pub fn clear(&mut self) {
-        self.bitmap.clear()
-    }
-
-    fn sip_new() -> SipHasher13 {
-        let mut rng = rand::thread_rng();
-        SipHasher13::new_with_keys(rand(&mut rng), rand(&mut rng))
+        self.bitmap.clear_bits()
     }
 }
 
-impl<T: Hash> BloomFilter<T> {
+impl<T: Hash, S: BuildHasher, B: BitmapStorage> BloomFilter<T, S, B> {
     // Add an item to the bloom filter
     pub fn set(&mut self, item: &T) {
         let mut hashes = [0u64, 0u64];
         
         for k in 0..self.k {
-            let offset = (self.bloom_hash(&mut hashes, &item, k) % self.bits) as usize;
-            self.bitmap.set(offset, true);
+            let offset = (self.bloom_hash(&mut hashes, item, k) % self.bits) as usize;
+            self.bitmap.set_bit(offset);
         }
     }
 
@@ -126,8 +382,8 @@ impl<T: Hash> BloomFilter<T> {
         let mut hashes = [0u64, 0u64];
 
         for k in 0..self.k {
-            let offset = (self.bloom_hash(&mut hashes, &item, k) % self.bits) as usize;
-            if !self.bitmap.get(offset).unwrap() {
+            let offset = (self.bloom_hash(&mut hashes, item, k) % self.bits) as usize;
+            if !self.bitmap.get_bit(offset) {
                 return false;
             }
         }
@@ -141,72 +397,313 @@ impl<T: Hash> BloomFilter<T> {
         let mut hashes = [0u64, 0u64];
 
         for k in 0..self.k {
-            let offset = (self.bloom_hash(&mut hashes, &item, k) % self.bits) as usize;
-            if !self.bitmap.get(offset).unwrap() {
+            let offset = (self.bloom_hash(&mut hashes, item, k) % self.bits) as usize;
+            if !self.bitmap.get_bit(offset) {
                 found = false;
-                self.bitmap.set(offset, true);
+                self.bitmap.set_bit(offset);
             }
         }
 
         found
     }
 
-    fn bloom_hash(&self, hashes: &mut [u64; 2], item: &T, k: u32) -> u64 {
-        if k < 2 {
-            let sip = &mut self.sips[k as usize].clone();
-            item.hash(sip);
+    // Add all the items to the bloom filter. Items are hashed in batches
+    // before any of their bits are set, so the hashing isn't stalled by
+    // cache misses on the bitmap, and the bitmap accesses of a batch can
+    // be in flight at the same time
+    pub fn set_all<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        let mut items = items.into_iter();
+        let mut offsets = Vec::with_capacity(BATCH_SIZE * self.k as usize);
+
+        while self.hash_batch(&mut items, &mut offsets) > 0 {
+            for &offset in &offsets {
+                self.bitmap.set_bit(offset);
+            }
+        }
+    }
 
-            let hash = sip.finish();
-            hashes[k as usize] = hash;
+    // Add all the items to the bloom filter like `set_all`, and return
+    // the previous state of each of them. An item that appears more than
+    // once is found the second time
+    pub fn check_and_set_all<I: IntoIterator<Item = T>>(&mut self, items: I) -> Vec<bool> {
+        let mut items = items.into_iter();
+        let mut offsets = Vec::with_capacity(BATCH_SIZE * self.k as usize);
+        let mut found = Vec::with_capacity(items.size_hint().0);
+
+        while self.hash_batch(&mut items, &mut offsets) > 0 {
+            for item_offsets in offsets.chunks(self.k as usize) {
+                let mut item_found = true;
+                for &offset in item_offsets {
+                    if !self.bitmap.get_bit(offset) {
+                        item_found = false;
+                        self.bitmap.set_bit(offset);
+                    }
+                }
+                found.push(item_found);
+            }
+        }
 
-            hash
-        } else {
-            hashes[0].wrapping_add((k as u64).wrapping_mul(hashes[1]) % 0xffffffffffffffc5)
+        found
+    }
+
+    // Check which of the items exist in the bloom filter, hashing them in
+    // batches like `set_all` does
+    // There can be false positives, but not false negatives
+    pub fn check_many(&self, items: &[T]) -> Vec<bool> {
+        let mut items = items.iter();
+        let mut offsets = Vec::with_capacity(BATCH_SIZE * self.k as usize);
+        let mut found = Vec::with_capacity(items.len());
+
+        while self.hash_batch(&mut items, &mut offsets) > 0 {
+            found.extend(
+                offsets
+                    .chunks(self.k as usize)
+                    .map(|item_offsets| item_offsets.iter().all(|&offset| self.bitmap.get_bit(offset))),
+            );
         }
+
+        found
+    }
+
+    // Replace `offsets` with the k bit offsets of each of the next
+    // `BATCH_SIZE` items, and return how many items there were
+    fn hash_batch<I>(&self, items: &mut I, offsets: &mut Vec<usize>) -> usize
+    where
+        I: Iterator,
+        I::Item: Borrow<T>,
+    {
+        offsets.clear();
+
+        let mut count = 0;
+        for item in items.take(BATCH_SIZE) {
+            let mut hashes = [0u64, 0u64];
+            for k in 0..self.k {
+                offsets.push((self.bloom_hash(&mut hashes, item.borrow(), k) % self.bits) as usize);
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    fn bloom_hash(&self, hashes: &mut [u64; 2], item: &T, k: u32) -> u64 {
+        hashing::bloom_hash(&self.hashers, hashes, item, k)
     }
 }
 
+// `f64::ln` and `f64::ceil` need std, so without it they come from libm
+#[cfg(feature = "std")]
+fn ln(x: f64) -> f64 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+fn ln(x: f64) -> f64 {
+    libm::log(x)
+}
+
+#[cfg(feature = "std")]
+fn ceil(x: f64) -> f64 {
+    x.ceil()
+}
+
+#[cfg(not(feature = "std"))]
+fn ceil(x: f64) -> f64 {
+    libm::ceil(x)
+}
+
+// Fixed keys for the tests, since filters can't pick random ones
+// without std
+#[cfg(test)]
+fn fixed_hashers() -> [SipBuildHasher; 2] {
+    [SipBuildHasher::with_keys(1, 2), SipBuildHasher::with_keys(3, 4)]
+}
+
 #[test]
 fn test_set() {
-    let mut bloom = BloomFilter::new(10, 80);
-    let key: &Vec<u8> = &rand::thread_rng().gen_iter()::<u8>().take(16).collect();
-    assert_eq!(bloom.check(key), false);
+    let mut bloom = BloomFilter::new_with_hashers(10, 80, fixed_hashers());
+    let key: &Vec<u8> = &rand::thread_rng().sample_iter(&rand::distributions::Standard).take(16).collect();
+    assert!(!bloom.check(key));
 
-    bloom.set(&key);
-    assert_eq!(bloom.check(key), true);
+    bloom.set(key);
+    assert!(bloom.check(key));
 }
 
 #[test]
 fn test_clear() {
-    let mut bloom = BloomFilter::new(10, 80);
-    let key: &Vec<u8> = &rand::thread_rng().gen_iter()::<u8>().take(16).collect();
+    let mut bloom = BloomFilter::new_with_hashers(10, 80, fixed_hashers());
+    let key: &Vec<u8> = &rand::thread_rng().sample_iter(&rand::distributions::Standard).take(16).collect();
     bloom.set(key);
-    assert_eq!(bloom.check(&key), true);
+    assert!(bloom.check(key));
 
     bloom.clear();
-    assert_eq!(bloom.check(&key), false);
+    assert!(!bloom.check(key));
 }
 
 #[test]
 fn test_check_and_set() {
-    let mut bloom = BloomFilter::new(10, 80);
-    let key: &Vec<u8> = &rand::thread_rng().gen_iter()::<u8>().take(16).collect();
-    assert_eq!(bloom.check_and_set(&key), false);
-    assert_eq!(bloom.check_and_set(&key), true);
+    let mut bloom = BloomFilter::new_with_hashers(10, 80, fixed_hashers());
+    let key: &Vec<u8> = &rand::thread_rng().sample_iter(&rand::distributions::Standard).take(16).collect();
+    assert!(!bloom.check_and_set(key));
+    assert!(bloom.check_and_set(key));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_custom_hashers() {
+    use std::collections::hash_map::RandomState;
+
+    let mut bloom = BloomFilter::new_for_fp_rate_with_hashers(100, 0.01, [RandomState::new(), RandomState::new()]);
+    for item in 0..100 {
+        bloom.set(&item);
+    }
+
+    assert!((0..100).all(|item| bloom.check(&item)));
+    let false_positives = (100..1100).filter(|item| bloom.check(item)).count();
+    assert!(false_positives < 50, "{} false positives", false_positives);
+}
+
+#[test]
+fn test_bytes_round_trip() {
+    let mut original = BloomFilter::new_with_hashers(10, 80, fixed_hashers());
+    let key: &Vec<u8> = &rand::thread_rng().sample_iter(&rand::distributions::Standard).take(16).collect();
+    original.set(key);
+
+    let bytes = original.to_bytes();
+    assert_eq!(bytes.len(), BYTES_HEADER_LEN + 10);
+
+    let loaded: BloomFilter<Vec<u8>> = BloomFilter::from_bytes(&bytes).unwrap();
+    assert!(loaded.check(key));
+    assert_eq!(loaded.number_of_bits(), 80);
+    assert_eq!(loaded.number_of_hashes(), original.number_of_hashes());
+    assert_eq!(loaded.sip_keys(), original.sip_keys());
+    assert_eq!(loaded.to_bytes(), bytes);
+}
+
+#[test]
+fn test_from_bytes_errors() {
+    let bytes = BloomFilter::<u32>::new_with_hashers(10, 80, fixed_hashers()).to_bytes();
+
+    let mut future = bytes.clone();
+    future[0] = 2;
+    assert_eq!(BloomFilter::<u32>::from_bytes(&future).err(), Some(FromBytesError::UnsupportedVersion(2)));
+    assert_eq!(BloomFilter::<u32>::from_bytes(&bytes[..20]).err(), Some(FromBytesError::InvalidLength));
+    assert_eq!(BloomFilter::<u32>::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(FromBytesError::InvalidLength));
+
+    let mut no_hashes = bytes;
+    no_hashes[9..13].copy_from_slice(&[0; 4]);
+    assert_eq!(BloomFilter::<u32>::from_bytes(&no_hashes).err(), Some(FromBytesError::EmptyFilter));
+}
+
+#[test]
+fn test_borrowed_storage() {
+    let keys = [(1, 2), (3, 4)];
+    let hashers = || [SipBuildHasher::with_keys(1, 2), SipBuildHasher::with_keys(3, 4)];
+    let mut region = vec![0u8; 16];
+
+    {
+        let mut bloom = BloomFilter::from_storage(&mut region[..], 5, hashers());
+        assert_eq!(bloom.number_of_bits(), 128);
+        bloom.set(&"mapped");
+        assert!(bloom.check(&"mapped"));
+        assert_eq!(bloom.sip_keys(), keys);
+    }
+    assert!(region.iter().any(|&byte| byte != 0), "Bits written in place");
+
+    let owned: BloomFilter<&str> = BloomFilter::from_existing(&region, 128, 5, keys);
+    assert!(owned.check(&"mapped"), "Same bit order as BitVec");
+
+    let bloom = BloomFilter::<&str, _, _>::from_storage(&mut region[..], 5, hashers());
+    assert!(bloom.check(&"mapped"), "Reopened from the same bytes");
+}
+
+#[test]
+fn test_set_all() {
+    let mut batched = BloomFilter::new_with_hashers(1000, 500, fixed_hashers());
+    let mut single = BloomFilter::from_existing(&[0; 1000], 8000, batched.number_of_hashes(), batched.sip_keys());
+
+    batched.set_all(0..500);
+    for item in 0..500 {
+        single.set(&item);
+    }
+    assert_eq!(batched.bitmap(), single.bitmap(), "Same bits as setting one by one");
+
+    let found = batched.check_and_set_all(vec![7, 1000, 1001, 1000]);
+    assert_eq!(found, [true, false, false, true]);
+    assert!(batched.check(&1001));
+}
+
+#[test]
+fn test_check_many() {
+    let mut bloom = BloomFilter::new_with_hashers(1000, 500, fixed_hashers());
+    bloom.set_all((0..500).map(|item| item * 2));
+
+    let items: Vec<u32> = (0..200).collect();
+    let found = bloom.check_many(&items);
+    assert_eq!(found.len(), 200);
+    assert!(found.iter().zip(&items).all(|(&found, item)| found == bloom.check(item)));
+    assert!(found.iter().step_by(2).all(|&found| found), "No false negatives");
+    assert!(bloom.check_many(&[]).is_empty());
+}
+
+#[test]
+fn test_try_merge() {
+    let keys = [(1, 2), (3, 4)];
+    let mut bloom = BloomFilter::from_existing(&[0; 8], 64, 3, keys);
+    let mut other = BloomFilter::from_existing(&[0; 8], 64, 3, keys);
+    bloom.set(&"apple");
+    other.set(&"banana");
+
+    assert_eq!(bloom.try_merge(&other), Ok(()));
+    assert!(bloom.check(&"apple") && bloom.check(&"banana"));
+    assert!(!other.check(&"apple"));
+
+    let wider = BloomFilter::from_existing(&[0; 16], 128, 3, keys);
+    assert_eq!(bloom.try_merge(&wider), Err(MergeError::BitsMismatch(64, 128)));
+    let more_hashes = BloomFilter::from_existing(&[0; 8], 64, 4, keys);
+    assert_eq!(bloom.try_merge(&more_hashes), Err(MergeError::HashesMismatch(3, 4)));
+    let rekeyed = BloomFilter::from_existing(&[0; 8], 64, 3, [(1, 2), (5, 6)]);
+    assert_eq!(bloom.try_merge(&rekeyed), Err(MergeError::KeysMismatch(keys, [(1, 2), (5, 6)])));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_write_read() {
+    let mut bloom = BloomFilter::from_existing(&[0; 8], 64, 3, [(1, 2), (3, 4)]);
+    bloom.set(&"apple");
+
+    let mut file = Vec::new();
+    bloom.write_to(&mut file).unwrap();
+    assert_eq!(&file[..4], b"BLMF");
+    assert_eq!(&file[4..], &bloom.to_bytes()[..]);
+
+    let read = BloomFilter::<&str>::read_from(&file[..]).unwrap();
+    assert!(read.check(&"apple"));
+    assert_eq!(read.to_bytes(), bloom.to_bytes());
+
+    let err = BloomFilter::<&str>::read_from(&file[..file.len() - 1]).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    let err = BloomFilter::<&str>::read_from(&file[4..]).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    file[4] = 9;
+    let err = BloomFilter::<&str>::read_from(&file[..]).err().unwrap();
+    let inner = err.get_ref().and_then(|err| err.downcast_ref::<FromBytesError>());
+    assert_eq!(inner, Some(&FromBytesError::UnsupportedVersion(9)));
 }
 
 #[test]
 fn test_load() {
-    let mut original = BloomFilter::new(10, 80);
-    let key: &Vec<u8> = &rand::thread_rng().gen_iter()::<u8>().take(16).collect();
-    original.set(&key);
-    assert_eq!(original.check(&key), true);
+    let mut original = BloomFilter::new_with_hashers(10, 80, fixed_hashers());
+    let key: &Vec<u8> = &rand::thread_rng().sample_iter(&rand::distributions::Standard).take(16).collect();
+    original.set(key);
+    assert!(original.check(key));
 
-    let cloned = Bloom::from_existing(
+    let cloned = BloomFilter::from_existing(
         &original.bitmap(),
         original.number_of_bits(),
         original.number_of_hashes(),
         original.sip_keys(),
     );
-    assert_eq!(cloned.check(&key), true);
+    assert!(cloned.check(key));
 }
@@ -0,0 +1,168 @@
+use core::hash::Hash;
+
+use crate::BloomFilter;
+
+// Each new filter is designed to hold this many times more items than
+// the one before it
+const GROWTH_FACTOR: usize = 2;
+// Each new filter's false positive rate is this many times the rate of
+// the one before it, so the rates form a series that sums to at most
+// the rate the scalable filter was created with
+const TIGHTENING_RATIO: f64 = 0.5;
+
+/// A Bloom filter that doesn't need to know how many items it will hold
+/// up front. It starts out as a single filter, and whenever the newest
+/// filter has held as many items as it was designed for, a larger one
+/// with a lower false positive rate is added. Items are only ever set in
+/// the newest filter, but are checked against all of them.
+pub struct ScalableBloomFilter<T> {
+    filters: Vec<BloomFilter<T>>,
+    // The number of items the first filter is designed to hold
+    initial_capacity: usize,
+    // The overall false positive rate the filter stays below
+    fp_rate: f64,
+    // The number of items set in the newest filter
+    newest_len: usize,
+    // The number of items set across all the filters
+    len: usize,
+}
+
+impl<T> ScalableBloomFilter<T> {
+    // Create a new scalable Bloom filter whose first filter holds
+    // `initial_capacity` items, and whose false positive rate stays below
+    // `fp_rate` no matter how many items are added. `fp_rate` must be
+    // between 0.0 and 1.0 exclusive
+    pub fn new(initial_capacity: usize, fp_rate: f64) -> Self {
+        assert!(initial_capacity > 0);
+        assert!(fp_rate > 0.0 && fp_rate < 1.0);
+
+        let mut filter = Self {
+            filters: Vec::new(),
+            initial_capacity,
+            fp_rate,
+            newest_len: 0,
+            len: 0,
+        };
+        filter.add_filter();
+
+        filter
+    }
+
+    // Return the number of items that have been set. Items that were
+    // false positives when they were set aren't counted
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Return the number of filters that have been chained so far
+    pub fn number_of_filters(&self) -> usize {
+        self.filters.len()
+    }
+
+    // Clear all the filters, removing all keys and shrinking back down
+    // to a single filter
+    pub fn clear(&mut self) {
+        self.filters.truncate(1);
+        self.filters[0].clear();
+        self.newest_len = 0;
+        self.len = 0;
+    }
+
+    // Chain a new filter, designed to hold more items with a lower false
+    // positive rate than the current newest one
+    fn add_filter(&mut self) {
+        let i = self.filters.len() as u32;
+        let capacity = self.initial_capacity * GROWTH_FACTOR.pow(i);
+        let fp_rate = self.fp_rate * (1.0 - TIGHTENING_RATIO) * TIGHTENING_RATIO.powi(i as i32);
+
+        self.filters.push(BloomFilter::new_for_fp_rate(capacity, fp_rate));
+        self.newest_len = 0;
+    }
+
+    fn newest_capacity(&self) -> usize {
+        self.initial_capacity * GROWTH_FACTOR.pow(self.filters.len() as u32 - 1)
+    }
+}
+
+impl<T: Hash> ScalableBloomFilter<T> {
+    // Add an item to the bloom filter
+    pub fn set(&mut self, item: &T) {
+        self.check_and_set(item);
+    }
+
+    // Check if an item exists in any of the filters
+    // There can be false positives, but not false negatives
+    pub fn check(&self, item: &T) -> bool {
+        self.filters.iter().any(|filter| filter.check(item))
+    }
+
+    // Add an item to the bloom filter and return the previous state of this item
+    pub fn check_and_set(&mut self, item: &T) -> bool {
+        if self.check(item) {
+            return true;
+        }
+
+        if self.newest_len == self.newest_capacity() {
+            self.add_filter();
+        }
+
+        self.filters
+            .last_mut()
+            .expect("there is always at least one filter")
+            .set(item);
+        self.newest_len += 1;
+        self.len += 1;
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grows() {
+        let mut bloom = ScalableBloomFilter::new(100, 0.01);
+        assert!(bloom.is_empty());
+        assert_eq!(bloom.number_of_filters(), 1);
+
+        for item in 0..10_000 {
+            bloom.set(&item);
+        }
+        assert!(bloom.number_of_filters() > 1, "Filters chained past the initial capacity");
+        assert!(bloom.len() > 9_500);
+
+        for item in 0..10_000 {
+            assert!(bloom.check(&item), "No false negatives");
+        }
+
+        let false_positives = (10_000..20_000).filter(|item| bloom.check(item)).count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_check_and_set() {
+        let mut bloom = ScalableBloomFilter::new(4, 0.01);
+        assert!(!bloom.check_and_set(&"alpha"));
+        assert!(bloom.check_and_set(&"alpha"));
+        assert_eq!(bloom.len(), 1);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut bloom = ScalableBloomFilter::new(10, 0.01);
+        for item in 0..100 {
+            bloom.set(&item);
+        }
+
+        bloom.clear();
+        assert!(bloom.is_empty());
+        assert_eq!(bloom.number_of_filters(), 1);
+        assert!(!bloom.check(&42));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::BloomFilter;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Serializes the filter as the byte blob returned by `to_bytes`, so the
+/// bitmap, the bit count, the number of hashes and the sip keys travel
+/// together in one versioned value.
+impl<T> Serialize for BloomFilter<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+/// Rebuilds a filter from a blob written by `to_bytes`. Formats without
+/// a native bytes type usually write bytes as a sequence, so both are
+/// accepted.
+impl<'de, T> Deserialize<'de> for BloomFilter<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(BytesVisitor(PhantomData))
+    }
+}
+
+struct BytesVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for BytesVisitor<T> {
+    type Value = BloomFilter<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bloom filter bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        BloomFilter::from_bytes(bytes).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        self.visit_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::BloomFilter;
+    use alloc::boxed::Box;
+    use serde::de::value::{BytesDeserializer, Error, SeqDeserializer};
+    use serde::Deserialize;
+    use serde_test::{assert_ser_tokens, Token};
+
+    fn filter_with(items: &[u32]) -> BloomFilter<u32> {
+        let mut bloom = BloomFilter::from_existing(&[0; 4], 32, 3, [(1, 2), (3, 4)]);
+        for item in items {
+            bloom.set(item);
+        }
+
+        bloom
+    }
+
+    #[test]
+    fn test_serialize() {
+        let bloom = filter_with(&[7, 11]);
+        let bytes = Box::leak(bloom.to_bytes().into_boxed_slice());
+
+        assert_ser_tokens(&bloom, &[Token::Bytes(bytes)]);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let bytes = filter_with(&[7, 11]).to_bytes();
+
+        let deserializer = BytesDeserializer::<Error>::new(&bytes);
+        let bloom = BloomFilter::<u32>::deserialize(deserializer).unwrap();
+        assert!(bloom.check(&7) && bloom.check(&11));
+        assert_eq!(bloom.sip_keys(), [(1, 2), (3, 4)]);
+
+        let deserializer = SeqDeserializer::<_, Error>::new(bytes.into_iter());
+        let bloom = BloomFilter::<u32>::deserialize(deserializer).unwrap();
+        assert!(bloom.check(&7), "Bytes written as a sequence");
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let deserializer = BytesDeserializer::<Error>::new(&[1, 2, 3]);
+        assert!(BloomFilter::<u32>::deserialize(deserializer).is_err());
+    }
+}
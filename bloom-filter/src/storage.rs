@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+use bit_vec::BitVec;
+
+/// The bits a `BloomFilter` sets and checks. A filter owns a `BitVec` by
+/// default, but can also work in place on a caller-provided `&mut [u8]`,
+/// such as a memory-mapped file or a shared memory segment, so a large
+/// filter never needs to be copied into memory of its own.
+///
+/// Bits are numbered from the most significant bit of the first byte,
+/// the same order `BitVec::from_bytes` uses, so the bytes returned by
+/// `BloomFilter::bitmap` can back a filter directly and vice versa.
+pub trait BitmapStorage {
+    // Return the number of bits available
+    fn number_of_bits(&self) -> usize;
+
+    fn get_bit(&self, index: usize) -> bool;
+
+    fn set_bit(&mut self, index: usize);
+
+    // Unset every bit
+    fn clear_bits(&mut self);
+
+    // Return the bits as bytes, in the order described above
+    fn to_bytes(&self) -> Vec<u8>;
+
+    // Set every bit that is set in `other`, which has the same number of
+    // bits. Storages that can do this a word at a time should override it
+    fn union_with(&mut self, other: &Self) {
+        for index in 0..other.number_of_bits() {
+            if other.get_bit(index) {
+                self.set_bit(index);
+            }
+        }
+    }
+}
+
+impl BitmapStorage for BitVec {
+    fn number_of_bits(&self) -> usize {
+        self.len()
+    }
+
+    fn get_bit(&self, index: usize) -> bool {
+        self[index]
+    }
+
+    fn set_bit(&mut self, index: usize) {
+        self.set(index, true);
+    }
+
+    fn clear_bits(&mut self) {
+        self.clear();
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        BitVec::to_bytes(self)
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        self.or(other);
+    }
+}
+
+impl BitmapStorage for &mut [u8] {
+    fn number_of_bits(&self) -> usize {
+        self.len() * 8
+    }
+
+    fn get_bit(&self, index: usize) -> bool {
+        self[index / 8] & mask(index) != 0
+    }
+
+    fn set_bit(&mut self, index: usize) {
+        self[index / 8] |= mask(index);
+    }
+
+    fn clear_bits(&mut self) {
+        self.fill(0);
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        for (byte, other) in self.iter_mut().zip(other.iter()) {
+            *byte |= other;
+        }
+    }
+}
+
+// The mask of a bit within its byte, counting from the most significant
+fn mask(index: usize) -> u8 {
+    0x80 >> (index % 8)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slice_matches_bitvec() {
+        let mut bytes = [0u8; 4];
+        let mut slice = &mut bytes[..];
+        let mut bits = BitVec::from_elem(32, false);
+
+        for index in [0, 3, 9, 17, 31] {
+            slice.set_bit(index);
+            bits.set_bit(index);
+        }
+
+        assert_eq!(slice.number_of_bits(), bits.number_of_bits());
+        assert_eq!(BitmapStorage::to_bytes(&slice), BitmapStorage::to_bytes(&bits));
+        assert!((0..32).all(|index| slice.get_bit(index) == bits.get_bit(index)));
+
+        slice.clear_bits();
+        assert_eq!(bytes, [0; 4]);
+    }
+
+    #[test]
+    fn test_union_with() {
+        let (mut ours, mut theirs) = ([0b1000_0001u8, 0], [0b0000_0011u8, 0x80]);
+        let mut bits = BitVec::from_bytes(&ours);
+
+        (&mut ours[..]).union_with(&&mut theirs[..]);
+        bits.union_with(&BitVec::from_bytes(&theirs));
+
+        assert_eq!(ours, [0b1000_0011, 0x80]);
+        assert_eq!(BitmapStorage::to_bytes(&bits), ours);
+    }
+}
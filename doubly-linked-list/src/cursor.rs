@@ -0,0 +1,203 @@
+use crate::{List, NodeHandle, NULL};
+
+/// A cursor over a `List` that can move back and forth through it and
+/// add or remove values where it stands, each in O(1) time.
+///
+/// Besides the nodes of the list, the cursor can stand on a "ghost"
+/// position between the back and the front of the list, so moving
+/// past either end wraps around through it. The cursor is on the
+/// ghost when the list is empty.
+pub struct CursorMut<'a, T> {
+    list: &'a mut List<T>,
+    /// Index of the current node, or `NULL` on the ghost
+    current: usize,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub(crate) fn new(list: &'a mut List<T>, current: usize) -> Self {
+        CursorMut { list, current }
+    }
+
+    /// Return a reference to the current value, or `None` on the ghost
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.current == NULL {
+            None
+        } else {
+            Some(&mut self.list.nodes[self.current].value)
+        }
+    }
+
+    /// Return a handle to the current node, or `None` on the ghost
+    pub fn current_handle(&self) -> Option<NodeHandle> {
+        if self.current == NULL {
+            None
+        } else {
+            Some(self.list.handle(self.current))
+        }
+    }
+
+    /// Move to the next node, or from the back of the list to the ghost,
+    /// or from the ghost to the front of the list
+    pub fn move_next(&mut self) {
+        self.current = if self.current == NULL {
+            self.list.head
+        } else {
+            self.list.nodes[self.current].next
+        };
+    }
+
+    /// Move to the previous node, or from the front of the list to the
+    /// ghost, or from the ghost to the back of the list
+    pub fn move_prev(&mut self) {
+        self.current = if self.current == NULL {
+            self.list.tail
+        } else {
+            self.list.nodes[self.current].prev
+        };
+    }
+
+    /// Insert a value before the current node, or at the back of the
+    /// list on the ghost, and return a handle to it
+    pub fn insert_before(&mut self, value: T) -> NodeHandle {
+        let prev = if self.current == NULL {
+            self.list.tail
+        } else {
+            self.list.nodes[self.current].prev
+        };
+
+        let node = self.list.insert_between(prev, self.current, value);
+        self.list.handle(node)
+    }
+
+    /// Insert a value after the current node, or at the front of the
+    /// list on the ghost, and return a handle to it
+    pub fn insert_after(&mut self, value: T) -> NodeHandle {
+        let next = if self.current == NULL {
+            self.list.head
+        } else {
+            self.list.nodes[self.current].next
+        };
+
+        let node = self.list.insert_between(self.current, next, value);
+        self.list.handle(node)
+    }
+
+    /// Remove the current node and return its value, moving to the next
+    /// node. Does nothing and returns `None` on the ghost
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.current == NULL {
+            return None;
+        }
+
+        let current = self.current;
+        self.current = self.list.nodes[current].next;
+
+        Some(self.list.remove_index(current))
+    }
+
+    /// Move every node after the current one into a new list and return
+    /// it, or the whole list on the ghost. Handles to the moved nodes are
+    /// no longer valid, except when the whole list is moved
+    pub fn split_after(&mut self) -> List<T> {
+        let first = if self.current == NULL {
+            self.list.head
+        } else {
+            self.list.nodes[self.current].next
+        };
+
+        if first == NULL {
+            List::new()
+        } else {
+            self.list.split_from(first)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn list_of(values: &[i32]) -> List<i32> {
+        let mut list = List::new();
+        for &value in values {
+            list.push_back(value);
+        }
+
+        list
+    }
+
+    fn values(list: &List<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_move() {
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None, "Moved onto the ghost");
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1), "Wrapped around");
+
+        cursor.move_prev();
+        assert_eq!(cursor.current_handle(), None);
+        cursor.move_prev();
+        *cursor.current().unwrap() = 30;
+        assert_eq!(values(&list), [1, 2, 30]);
+
+        let mut empty = List::<i32>::new();
+        let mut cursor = empty.cursor_back_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut list = list_of(&[1, 3]);
+        let mut cursor = list.cursor_front_mut();
+
+        cursor.insert_before(0);
+        let two = cursor.insert_after(2);
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current_handle(), Some(two));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), None, "Nothing to remove on the ghost");
+        cursor.insert_before(4);
+        cursor.insert_after(-1);
+        assert_eq!(values(&list), [-1, 0, 2, 3, 4]);
+
+        let mut list = List::new();
+        list.cursor_front_mut().insert_after(1);
+        assert_eq!(values(&list), [1]);
+        assert_eq!(list.peek_back(), Some(&1));
+    }
+
+    #[test]
+    fn test_split_after() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+
+        let back = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(values(&list), [1, 2]);
+        assert_eq!(values(&back), [3, 4]);
+        assert_eq!(list.peek_back(), Some(&2));
+
+        let mut cursor = list.cursor_back_mut();
+        assert!(cursor.split_after().is_empty());
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(values(&all), [1, 2]);
+        assert!(list.is_empty());
+    }
+}
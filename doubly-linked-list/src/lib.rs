@@ -1,38 +1,172 @@
-#![allow(dead_code)]
+//! A doubly-linked list whose nodes live in a `Slab` instead of being
+//! individually boxed, so nodes are linked by index rather than by pointer.
+//!
+//! Pushing, popping and peeking at either end take O(1) time, as does
+//! removing any node given the `NodeHandle` returned when it was pushed.
+//! Pushing is amortized O(1), since the slab grows like a `Vec`.
+//!
+//! Each list has a slab of its own, so moving nodes from one list to
+//! another means moving their values to the other slab. `append` and
+//! `split_off` take time proportional to the number of values they move,
+//! and handles to moved values are no longer valid. The lists of a
+//! `ListPool` share one slab instead, so nodes move between them by
+//! relinking: appending takes O(1) time there, `move_run` moves a run of
+//! nodes between lists, and handles stay valid.
 
 extern crate slab;
 
 use slab::Slab;
+use std::cmp::Ordering as CmpOrdering;
+use std::iter::{FromIterator, Rev};
+use std::ops::{Index, IndexMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod cursor;
+mod pool;
+mod sync;
+
+pub use cursor::CursorMut;
+pub use pool::{ListId, ListPool, PoolIter};
+pub use sync::SyncList;
 
 /// The null index here is a safe replacement for a null pointer
 /// `!0` is the largest possible value that can be stored in a usize
 const NULL: usize = !0;
 
+/// Generation assigned to the next node created by any list. It is
+/// shared by all lists so that a handle to a node that moved to another
+/// list can't resolve to an unrelated node there
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 struct Node<T> {
     value: T,
     prev: usize,
     next: usize,
+    /// Generation of the value stored in this node
+    generation: u64,
 }
 
-struct List<T> {
+/// A doubly-linked list of values of type `T`.
+pub struct List<T> {
     nodes: Slab<Node<T>>,
     head: usize,
     tail: usize,
+    /// Number of nodes taken out of the list by `unlink`, which are
+    /// still in the slab
+    unlinked: usize,
+}
+
+/// A reference to a node of a `List`, returned when a value is pushed.
+/// A handle stops resolving once its node is removed, even if the slot
+/// in the slab is later reused by another node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    /// Index of the node in the slab
+    index: usize,
+    /// Generation of the node at the time it was created
+    generation: u64,
+}
+
+/// An iterator that moves values out of a `List`, from front to back.
+pub struct IntoIter<T>(List<T>);
+
+/// An iterator over references to the values of a `List`, from front
+/// to back.
+pub struct Iter<'a, T> {
+    list: &'a List<T>,
+    /// Index of the next node to yield from the front
+    front: usize,
+    /// Index of the next node to yield from the back
+    back: usize,
+    /// Number of nodes not yet yielded from either end
+    remaining: usize,
+}
+
+/// An iterator over mutable references to the values of a `List`, from
+/// front to back.
+pub struct IterMut<'a, T> {
+    list: &'a mut List<T>,
+    front: usize,
+    back: usize,
+    remaining: usize,
 }
 
-struct IntoIter<T>(List<T>);
+/// Restores a list whose `next` links `sort_by` was rewiring when its
+/// comparator panicked
+struct SortGuard<'a, T>(&'a mut List<T>);
+
+impl<T> Drop for SortGuard<'_, T> {
+    fn drop(&mut self) {
+        let list = &mut *self.0;
+        let mut next = NULL;
+        let mut index = list.tail;
+        while index != NULL {
+            list.nodes[index].next = next;
+            next = index;
+            index = list.nodes[index].prev;
+        }
+    }
+}
+
+/// An iterator that removes the values of a `List` that match a filter
+/// and yields them, from front to back. Returned by `drain_filter`.
+pub struct DrainFilter<'a, T, F: FnMut(&mut T) -> bool> {
+    list: &'a mut List<T>,
+    /// Index of the next node to pass to the filter
+    next: usize,
+    filter: F,
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl<T> List<T> {
-    fn new() -> Self {
+    /// Create an empty list
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Create an empty list with room for `capacity` values before its
+    /// slab needs to grow
+    pub fn with_capacity(capacity: usize) -> Self {
         List {
-            nodes: Slab::new(),
+            nodes: Slab::with_capacity(capacity),
             head: NULL,
             tail: NULL,
+            unlinked: 0,
         }
     }
 
-    fn len(&self) -> usize {
-        self.nodes.len()
+    /// Return the number of values the list can hold without growing
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Make room for at least `additional` more values
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// Release as much unused capacity as possible. Nodes never move
+    /// within the slab, so it can't shrink below the highest slot still
+    /// in use, but an empty list gives back all of its memory
+    pub fn shrink_to_fit(&mut self) {
+        if self.nodes.is_empty() {
+            self.nodes.clear();
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Return the number of values in the list
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.unlinked
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Link two nodes together such that `a.next` is `b` and `b.prev` is `a`
@@ -41,44 +175,245 @@ impl<T> List<T> {
         if b != NULL { self.nodes[b].prev = a; }
     }
 
-    fn push_back(&mut self, value: T) -> usize {
-        let node = self.nodes.insert(Node {
-            value,
-            prev: NULL,
-            next: NULL,
-        });
+    /// Insert a value between two adjacent nodes, either of which can be
+    /// `NULL` at the ends of the list, and return the index of its node
+    fn insert_between(&mut self, prev: usize, next: usize, value: T) -> usize {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        let node = self.nodes.insert(Node { value, prev, next, generation });
+        self.link_between(prev, next, node);
+
+        node
+    }
+
+    /// Link the node at `index` between two adjacent nodes, either of
+    /// which can be `NULL` at the ends of the list
+    fn link_between(&mut self, prev: usize, next: usize, index: usize) {
+        self.link(prev, index);
+        self.link(index, next);
+        if prev == NULL { self.head = index; }
+        if next == NULL { self.tail = index; }
+    }
+
+    /// Link the neighbours of the node at `index` to each other, taking
+    /// it out of the list while leaving it in the slab
+    fn unlink_index(&mut self, index: usize) {
+        let (prev, next) = (self.nodes[index].prev, self.nodes[index].next);
+
+        self.link(prev, next);
+        if self.head == index { self.head = next; }
+        if self.tail == index { self.tail = prev; }
+
+        let node = &mut self.nodes[index];
+        node.prev = NULL;
+        node.next = NULL;
+    }
+
+    /// Return the index of the node `n` places from the front, walking
+    /// from whichever end of the list is closer
+    fn nth_index(&self, n: usize) -> usize {
+        if n < self.len() / 2 {
+            (0..n).fold(self.head, |index, _| self.nodes[index].next)
+        } else {
+            (n + 1..self.len()).fold(self.tail, |index, _| self.nodes[index].prev)
+        }
+    }
 
-        let tail = self.tail;
-        self.link(tail, node);
+    /// Move the node at `first` and every node after it into a new list,
+    /// in the same order. Each node moved to the new list gets a new
+    /// index, so handles to them are no longer valid
+    fn split_from(&mut self, first: usize) -> List<T> {
+        if first == self.head && self.unlinked == 0 {
+            return std::mem::take(self);
+        }
 
-        self.tail = node;
-        if self.head == NULL {
-            self.head = node;
+        let mut other = List::new();
+        let last = self.nodes[first].prev;
+        let mut index = first;
+        while index != NULL {
+            let node = self.nodes.remove(index);
+            other.push_back(node.value);
+            index = node.next;
         }
 
-        node
+        self.link(last, NULL);
+        self.tail = last;
+        if last == NULL {
+            self.head = NULL;
+        }
+
+        other
+    }
+
+    /// Return the index of the node a handle refers to, if it is still
+    /// in the list
+    fn handle_index(&self, handle: NodeHandle) -> Option<usize> {
+        match self.nodes.get(handle.index) {
+            Some(node) if node.generation == handle.generation => Some(handle.index),
+            _ => None,
+        }
+    }
+
+    /// Return the index of the node a handle refers to, if it is still
+    /// in the list and wasn't taken out of it by `unlink`
+    fn linked_index(&self, handle: NodeHandle) -> Option<usize> {
+        self.handle_index(handle).filter(|&index| self.is_linked(index))
+    }
+
+    /// Return whether the node at `index` is linked into the list. Every
+    /// linked node but the head has a previous node
+    fn is_linked(&self, index: usize) -> bool {
+        self.head == index || self.nodes[index].prev != NULL
+    }
+
+    /// Return a handle to the node at `index`
+    fn handle(&self, index: usize) -> NodeHandle {
+        NodeHandle {
+            index,
+            generation: self.nodes[index].generation,
+        }
+    }
+
+    /// Add a value to the back of the list, and return a handle to it
+    pub fn push_back(&mut self, value: T) -> NodeHandle {
+        let node = self.insert_between(self.tail, NULL, value);
+        self.handle(node)
+    }
+
+    /// Add a value to the front of the list, and return a handle to it
+    pub fn push_front(&mut self, value: T) -> NodeHandle {
+        let node = self.insert_between(NULL, self.head, value);
+        self.handle(node)
     }
 
-    fn push_front(&mut self, value: T) -> usize {
-        let node = self.nodes.insert(Node {
-            value,
-            prev: NULL,
-            next: NULL,
-        });
+    /// Insert a value right after the node the handle refers to, and
+    /// return a handle to it. Returns `None`, dropping the value, if the
+    /// node was removed
+    pub fn insert_after(&mut self, handle: NodeHandle, value: T) -> Option<NodeHandle> {
+        let prev = self.linked_index(handle)?;
+        let node = self.insert_between(prev, self.nodes[prev].next, value);
 
-        let head = self.head;
-        self.link(node, head);
+        Some(self.handle(node))
+    }
+
+    /// Insert a value right before the node the handle refers to, like
+    /// `insert_after`
+    pub fn insert_before(&mut self, handle: NodeHandle, value: T) -> Option<NodeHandle> {
+        let next = self.linked_index(handle)?;
+        let node = self.insert_between(self.nodes[next].prev, next, value);
+
+        Some(self.handle(node))
+    }
 
-        self.head = node;
-        if self.tail == NULL {
-            self.tail = node;
+    /// Move the first `n` values to the back of the list, in order, so
+    /// that `rotate_forward(1)` turns the list round-robin style. `n` can
+    /// be larger than the list, and wraps around. Only the ends of the
+    /// list are relinked, but finding the new front takes O(min(n, len - n))
+    pub fn rotate_forward(&mut self, n: usize) {
+        if self.is_empty() {
+            return;
+        }
+        let n = n % self.len();
+        if n == 0 {
+            return;
         }
 
-        node
+        let head = self.nth_index(n);
+        let tail = self.nodes[head].prev;
+
+        self.link(self.tail, self.head);
+        self.link(tail, NULL);
+        self.link(NULL, head);
+        self.head = head;
+        self.tail = tail;
     }
 
-    fn pop_back(&mut self) -> Option<T> {
-        if self.len() == 0 {
+    /// Move the last `n` values to the front of the list, in order. This
+    /// undoes `rotate_forward(n)`
+    pub fn rotate_backward(&mut self, n: usize) {
+        if !self.is_empty() {
+            self.rotate_forward(self.len() - n % self.len());
+        }
+    }
+
+    /// Move the node the handle refers to to the front of the list in O(1)
+    /// time, keeping its handle. Returns false if the node isn't in the
+    /// list
+    pub fn move_to_front(&mut self, handle: NodeHandle) -> bool {
+        match self.linked_index(handle) {
+            Some(index) => {
+                self.unlink_index(index);
+                self.link_between(NULL, self.head, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the node the handle refers to to the back of the list, like
+    /// `move_to_front`
+    pub fn move_to_back(&mut self, handle: NodeHandle) -> bool {
+        match self.linked_index(handle) {
+            Some(index) => {
+                self.unlink_index(index);
+                self.link_between(self.tail, NULL, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the node the handle refers to out of the list in O(1) time,
+    /// without dropping or moving its value. The node keeps its handle,
+    /// and its value can still be read with `get`, but it is skipped by
+    /// everything that walks the list and isn't counted by `len` until
+    /// it is put back with `relink_front` or `relink_back`, or dropped
+    /// with `remove`. Returns false if the node isn't in the list
+    pub fn unlink(&mut self, handle: NodeHandle) -> bool {
+        match self.linked_index(handle) {
+            Some(index) => {
+                self.unlink_index(index);
+                self.unlinked += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put a node taken out by `unlink` back at the front of the list in
+    /// O(1) time. Returns false if the node was removed or wasn't unlinked
+    pub fn relink_front(&mut self, handle: NodeHandle) -> bool {
+        match self.unlinked_index(handle) {
+            Some(index) => {
+                self.unlinked -= 1;
+                self.link_between(NULL, self.head, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put a node taken out by `unlink` back at the back of the list, like
+    /// `relink_front`
+    pub fn relink_back(&mut self, handle: NodeHandle) -> bool {
+        match self.unlinked_index(handle) {
+            Some(index) => {
+                self.unlinked -= 1;
+                self.link_between(self.tail, NULL, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the index of the node a handle refers to, if it was taken
+    /// out of the list by `unlink`
+    fn unlinked_index(&self, handle: NodeHandle) -> Option<usize> {
+        self.handle_index(handle).filter(|&index| !self.is_linked(index))
+    }
+
+    /// Remove the value at the back of the list and return it
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
             None
         } else {
             let node = self.nodes.remove(self.tail);
@@ -94,8 +429,9 @@ impl<T> List<T> {
         }
     }
 
-    fn pop_front(&mut self) -> Option<T> {
-        if self.len() == 0 {
+    /// Remove the value at the front of the list and return it
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
             None
         } else {
             let node = self.nodes.remove(self.head);
@@ -111,7 +447,18 @@ impl<T> List<T> {
         }
     }
 
-    fn remove(&mut self, index: usize) -> T {
+    /// Remove the node the handle refers to and return its value, or
+    /// `None` if it was already removed
+    pub fn remove(&mut self, handle: NodeHandle) -> Option<T> {
+        let index = self.handle_index(handle)?;
+        Some(self.remove_index(index))
+    }
+
+    /// Remove the node at `index` and return its value
+    fn remove_index(&mut self, index: usize) -> T {
+        if !self.is_linked(index) {
+            self.unlinked -= 1;
+        }
         let node = self.nodes.remove(index);
 
         self.link(node.prev, node.next);
@@ -121,8 +468,161 @@ impl<T> List<T> {
         node.value
     }
 
-    fn peek_front(&self) -> Option<&T> {
-       if self.len() == 0 {
+    /// Keep only the values for which `f` returns true, removing the
+    /// others in a single pass from front to back
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut index = self.head;
+        while index != NULL {
+            let next = self.nodes[index].next;
+            if !f(&self.nodes[index].value) {
+                self.remove_index(index);
+            }
+            index = next;
+        }
+    }
+
+    /// Sort the list in ascending order. The sort is stable, and nodes
+    /// are only relinked, so handles stay valid
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp)
+    }
+
+    /// Sort the list with a comparator function, like `sort`. This is a
+    /// bottom-up merge sort that takes O(n log n) time and doesn't
+    /// allocate
+    pub fn sort_by<F: FnMut(&T, &T) -> CmpOrdering>(&mut self, mut compare: F) {
+        if self.is_empty() {
+            return;
+        }
+
+        // Merge pairs of sorted runs of `size` nodes into runs of twice
+        // that, until a single run is left. Only the `next` links are
+        // kept up to date while merging, so if `compare` panics, the
+        // guard puts the list back in its original order from the
+        // untouched `prev` links
+        let guard = SortGuard(self);
+        let list = &mut *guard.0;
+        let mut head = list.head;
+        let mut size = 1;
+        loop {
+            let mut left = head;
+            let mut tail = NULL;
+            let mut merges = 0;
+            head = NULL;
+
+            while left != NULL {
+                merges += 1;
+
+                let mut right = left;
+                let mut left_size = 0;
+                while left_size < size && right != NULL {
+                    left_size += 1;
+                    right = list.nodes[right].next;
+                }
+                let mut right_size = size;
+
+                while left_size > 0 || (right_size > 0 && right != NULL) {
+                    // Take from the left run on ties to keep the sort stable
+                    let take_left = right_size == 0
+                        || right == NULL
+                        || (left_size > 0
+                            && compare(&list.nodes[left].value, &list.nodes[right].value) != CmpOrdering::Greater);
+
+                    let node = if take_left {
+                        let node = left;
+                        left = list.nodes[left].next;
+                        left_size -= 1;
+                        node
+                    } else {
+                        let node = right;
+                        right = list.nodes[right].next;
+                        right_size -= 1;
+                        node
+                    };
+
+                    if tail == NULL { head = node; } else { list.nodes[tail].next = node; }
+                    tail = node;
+                }
+
+                left = right;
+            }
+
+            list.nodes[tail].next = NULL;
+            if merges <= 1 {
+                break;
+            }
+            size *= 2;
+        }
+
+        std::mem::forget(guard);
+
+        // Restore the `prev` links along the sorted order
+        let mut prev = NULL;
+        let mut index = head;
+        while index != NULL {
+            self.nodes[index].prev = prev;
+            prev = index;
+            index = self.nodes[index].next;
+        }
+        self.head = head;
+        self.tail = prev;
+    }
+
+    /// Return an iterator that removes each value for which `filter`
+    /// returns true and yields it. Values are filtered as the iterator
+    /// advances, and if it is dropped early, the rest of the matching
+    /// values are removed then. The filter can modify the values it sees
+    pub fn drain_filter<F: FnMut(&mut T) -> bool>(&mut self, filter: F) -> DrainFilter<'_, T, F> {
+        DrainFilter {
+            next: self.head,
+            list: self,
+            filter,
+        }
+    }
+
+    /// Return a handle to the first node from the front whose value
+    /// matches the predicate
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut predicate: P) -> Option<NodeHandle> {
+        let mut index = self.head;
+        while index != NULL {
+            let node = &self.nodes[index];
+            if predicate(&node.value) {
+                return Some(self.handle(index));
+            }
+            index = node.next;
+        }
+
+        None
+    }
+
+    /// Return whether the list holds a value equal to the given one
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|v| v == value)
+    }
+
+    /// Return a reference to the value of the node the handle refers to,
+    /// or `None` if it was removed
+    pub fn get(&self, handle: NodeHandle) -> Option<&T> {
+        let index = self.handle_index(handle)?;
+        Some(&self.nodes[index].value)
+    }
+
+    /// Return a mutable reference to the value of the node the handle
+    /// refers to, or `None` if it was removed
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        let index = self.handle_index(handle)?;
+        Some(&mut self.nodes[index].value)
+    }
+
+    /// Return a reference to the value at the front of the list
+    pub fn peek_front(&self) -> Option<&T> {
+       if self.is_empty() {
            None
        } else {
            let node = self.nodes.get(self.head).unwrap();
@@ -130,8 +630,9 @@ impl<T> List<T> {
        }
     }
 
-    fn peek_back(&self) -> Option<&T> {
-        if self.len() == 0 {
+    /// Return a reference to the value at the back of the list
+    pub fn peek_back(&self) -> Option<&T> {
+        if self.is_empty() {
             None
         } else {
             let node = self.nodes.get(self.tail).unwrap();
@@ -139,8 +640,93 @@ impl<T> List<T> {
         }
     }
 
-    fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    /// Move all the values of `other` to the back of this list, leaving
+    /// `other` empty. Handles to nodes of this list stay valid. This takes
+    /// O(1) time if this list is empty, and O(`other.len()`) time otherwise.
+    /// Lists of a `ListPool` can always be appended in O(1) time
+    pub fn append(&mut self, other: &mut List<T>) {
+        if self.nodes.is_empty() {
+            std::mem::swap(self, other);
+            return;
+        }
+
+        while let Some(value) = other.pop_front() {
+            self.push_back(value);
+        }
+    }
+
+    /// Split the list in two at the given node, returning that node and
+    /// every node after it as a new list. Handles to nodes before it stay
+    /// valid. This takes time proportional to the length of the new list,
+    /// while `ListPool::split_off` only relinks nodes.
+    /// Panics if the node was already removed
+    pub fn split_off(&mut self, at: NodeHandle) -> List<T> {
+        let at = self.linked_index(at).expect("split_off at a node not in the list");
+        self.split_from(at)
+    }
+
+    /// Return a cursor at the front of the list, which can move through
+    /// it and edit it in place
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut::new(self, self.head)
+    }
+
+    /// Return a cursor at the back of the list
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut::new(self, self.tail)
+    }
+
+    /// Move the values into a vector, from front to back
+    pub fn into_vec(self) -> Vec<T> {
+        self.into_iter().collect()
+    }
+
+    /// Copy the values into a vector, from front to back
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    /// Return an iterator over the values, from front to back
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len(),
+        }
+    }
+
+    /// Return an iterator over the values from back to front, following
+    /// the `prev` links. This is the same as `iter().rev()`
+    pub fn rev_iter(&self) -> Rev<Iter<'_, T>> {
+        self.iter().rev()
+    }
+
+    /// Reverse the order of the list in O(n) time by swapping the links
+    /// of every node. No values move, so handles stay valid
+    pub fn reverse(&mut self) {
+        let mut index = self.head;
+        while index != NULL {
+            let node = &mut self.nodes[index];
+            std::mem::swap(&mut node.prev, &mut node.next);
+            index = node.prev;
+        }
+
+        std::mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    /// Return an iterator that allows modifying each value, from front
+    /// to back
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len(),
+            list: self,
+        }
     }
 }
 
@@ -150,12 +736,73 @@ impl<T> Drop for List<T> {
     }
 }
 
+/// Panics if the node the handle refers to was removed
+impl<T> Index<NodeHandle> for List<T> {
+    type Output = T;
+
+    fn index(&self, handle: NodeHandle) -> &T {
+        self.get(handle).expect("no node for handle")
+    }
+}
+
+/// Panics if the node the handle refers to was removed
+impl<T> IndexMut<NodeHandle> for List<T> {
+    fn index_mut(&mut self, handle: NodeHandle) -> &mut T {
+        self.get_mut(handle).expect("no node for handle")
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.nodes.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for List<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+
+        list
+    }
+}
+
+impl<T> From<Vec<T>> for List<T> {
+    fn from(values: Vec<T>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<T> {
         self.0.pop_front()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
 }
 
 impl<T> DoubleEndedIterator for IntoIter<T> {
@@ -164,6 +811,139 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for DrainFilter<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.next != NULL {
+            let index = self.next;
+            let node = &mut self.list.nodes[index];
+            self.next = node.next;
+
+            if (self.filter)(&mut node.value) {
+                return Some(self.list.remove_index(index));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.list.len()))
+    }
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Drop for DrainFilter<'_, T, F> {
+    fn drop(&mut self) {
+        // Don't call a filter that may have just panicked again
+        if !std::thread::panicking() {
+            self.for_each(drop);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = &self.list.nodes[self.front];
+        self.front = node.next;
+        self.remaining -= 1;
+
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = &self.list.nodes[self.back];
+        self.back = node.prev;
+        self.remaining -= 1;
+
+        Some(&node.value)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> IterMut<'a, T> {
+    /// Return a reference to the value at `index` that outlives the
+    /// borrow of the iterator
+    fn value_at(&mut self, index: usize) -> &'a mut T {
+        // Use a raw pointer here because the compiler doesn't know that
+        // `remaining` stops both ends from ever yielding the same node
+        unsafe { &mut *(&mut self.list.nodes[index].value as *mut T) }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.front;
+        self.front = self.list.nodes[index].next;
+        self.remaining -= 1;
+
+        Some(self.value_at(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.back;
+        self.back = self.list.nodes[index].prev;
+        self.remaining -= 1;
+
+        Some(self.value_at(index))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
 #[test]
 fn basics() {
     let mut list = List::new();
@@ -215,8 +995,8 @@ fn test_peek() {
     list.push_front(2);
     list.push_front(3);
 
-    assert_eq!(&*list.peek_front().unwrap(), &3);
-    assert_eq!(&*list.peek_back().unwrap(), &1); 
+    assert_eq!(list.peek_front(), Some(&3));
+    assert_eq!(list.peek_back(), Some(&1));
 }
 
 #[test]
@@ -228,9 +1008,477 @@ fn test_into_iter() {
     list.push_front(3);
 
     let mut iter = list.into_iter();
+    assert_eq!(iter.len(), 3);
     assert_eq!(iter.next(), Some(3));
     assert_eq!(iter.next_back(), Some(1));
     assert_eq!(iter.next(), Some(2));
     assert_eq!(iter.next_back(), None);
     assert_eq!(iter.next(), None);
-}
\ No newline at end of file
+}
+#[test]
+fn test_remove() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+
+    let one = list.push_back(1);
+    let two = list.push_back(2);
+    let three = list.push_back(3);
+
+    assert_eq!(list.remove(two), Some(2));
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.remove(one), Some(1));
+    assert_eq!(list.peek_front(), Some(&3));
+    assert_eq!(list.remove(three), Some(3));
+    assert!(list.is_empty());
+    assert_eq!(list.peek_back(), None);
+}
+
+#[test]
+fn test_iter() {
+    let mut list = List::new();
+    assert_eq!(list.iter().next(), None);
+
+    list.push_back(1);
+    let two = list.push_back(2);
+    list.push_back(3);
+    list.push_front(0);
+    list.remove(two);
+
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&0, &1, &3]);
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), [&3, &1, &0]);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next(), Some(&0));
+    assert_eq!(iter.next_back(), Some(&3));
+    assert_eq!(iter.next(), Some(&1));
+    assert_eq!(iter.next_back(), None);
+    assert_eq!(list.len(), 3);
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut list = List::new();
+    list.push_back(1);
+    list.push_back(2);
+    list.push_back(3);
+
+    for value in &mut list {
+        *value *= 10;
+    }
+
+    let mut iter = list.iter_mut();
+    *iter.next_back().unwrap() += 1;
+    *iter.next().unwrap() += 2;
+    assert_eq!(iter.len(), 1);
+
+    assert_eq!((&list).into_iter().collect::<Vec<_>>(), [&12, &20, &31]);
+}
+
+#[test]
+fn test_append() {
+    let mut list = List::new();
+    let mut other = List::new();
+    let one = list.push_back(1);
+    other.push_back(2);
+    other.push_back(3);
+
+    list.append(&mut other);
+    assert!(other.is_empty());
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+    assert_eq!(list.remove(one), Some(1), "Handles into self stay valid");
+
+    let mut empty = List::new();
+    empty.append(&mut list);
+    assert_eq!(empty.iter().collect::<Vec<_>>(), [&2, &3]);
+    assert!(list.is_empty());
+
+    list.append(&mut List::new());
+    assert!(list.is_empty());
+}
+
+#[test]
+fn test_split_off() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    let two = list.push_back(2);
+    list.push_back(3);
+
+    let back = list.split_off(two);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1]);
+    assert_eq!(back.iter().collect::<Vec<_>>(), [&2, &3]);
+    assert_eq!(list.peek_back(), Some(&1));
+
+    let all = list.split_off(one);
+    assert!(list.is_empty());
+    assert_eq!(all.iter().collect::<Vec<_>>(), [&1]);
+}
+
+#[test]
+fn test_stale_handles() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    assert_eq!(list.get(one), Some(&1));
+
+    assert_eq!(list.remove(one), Some(1));
+    assert_eq!(list.remove(one), None);
+
+    let two = list.push_back(2);
+    assert_eq!(list.get(one), None, "Reused slot doesn't resolve the old handle");
+    assert_eq!(list.remove(one), None);
+    assert_eq!(list.get(two), Some(&2));
+
+    let mut other = List::new();
+    other.push_back(3);
+    let four = other.push_back(4);
+    list.append(&mut other);
+    assert_eq!(list.get(four), None, "Moved nodes get new handles");
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&2, &3, &4]);
+}
+
+#[test]
+fn test_retain() {
+    let mut list = List::new();
+    for value in 0..10 {
+        list.push_back(value);
+    }
+    let seven = list.push_back(7);
+
+    list.retain(|value| value % 3 != 0);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &4, &5, &7, &8, &7]);
+    assert_eq!(list.get(seven), Some(&7), "Kept nodes keep their handles");
+
+    list.retain(|_| false);
+    assert!(list.is_empty());
+    assert_eq!(list.peek_front(), None);
+    assert_eq!(list.peek_back(), None);
+}
+
+#[test]
+fn test_find() {
+    let mut list = List::new();
+    list.push_back(1);
+    let two = list.push_back(2);
+    list.push_back(3);
+    list.push_back(2);
+
+    assert_eq!(list.find(|&value| value == 2), Some(two), "Finds the first match");
+    assert_eq!(list.find(|&value| value > 5), None);
+    assert!(list.contains(&3));
+    assert!(!list.contains(&4));
+
+    let three = list.find(|&value| value == 3).unwrap();
+    assert_eq!(list.remove(three), Some(3));
+    assert!(!list.contains(&3));
+}
+
+#[test]
+fn test_sort() {
+    let mut list = List::new();
+    list.sort();
+    assert!(list.is_empty());
+
+    let values = [5, 3, 9, 1, 3, 7, 0, 8, 2, 6, 4];
+    let handles: Vec<_> = values.iter().map(|&value| list.push_back(value)).collect();
+    list.sort();
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&0, &1, &2, &3, &3, &4, &5, &6, &7, &8, &9]);
+    let backwards: Vec<_> = list.iter().rev().copied().collect();
+    assert_eq!(backwards, [9, 8, 7, 6, 5, 4, 3, 3, 2, 1, 0], "Prev links follow the new order");
+    assert_eq!(list.peek_front(), Some(&0));
+    assert_eq!(list.get(handles[2]), Some(&9), "Handles stay valid");
+
+    list.sort_by(|a, b| b.cmp(a));
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), [9, 8, 7, 6, 5, 4, 3, 3, 2, 1, 0]);
+}
+
+#[test]
+fn test_sort_is_stable() {
+    let mut list = List::new();
+    for (i, key) in [2, 1, 2, 0, 1, 2, 0].iter().enumerate() {
+        list.push_back((*key, i));
+    }
+
+    list.sort_by(|a, b| a.0.cmp(&b.0));
+    let sorted: Vec<_> = list.iter().copied().collect();
+    assert_eq!(sorted, [(0, 3), (0, 6), (1, 1), (1, 4), (2, 0), (2, 2), (2, 5)]);
+}
+
+#[test]
+fn test_from_iter() {
+    let mut list: List<_> = (1..4).collect();
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+
+    list.extend(vec![4, 5]);
+    list.extend(&[6]);
+    assert_eq!(list.len(), 6);
+    assert_eq!(list.peek_back(), Some(&6));
+
+    let list = List::from(vec!["a", "b"]);
+    assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), ["b", "a"]);
+}
+
+#[test]
+fn test_capacity() {
+    let mut list = List::with_capacity(10);
+    assert!(list.capacity() >= 10);
+
+    list.reserve(100);
+    assert!(list.capacity() >= 100);
+    list.extend(0..100);
+    let last = list.find(|&value| value == 99).unwrap();
+
+    list.retain(|&value| value < 10 || value == 99);
+    list.shrink_to_fit();
+    assert!(list.capacity() >= 100, "Can't shrink below the last node");
+    assert_eq!(list.get(last), Some(&99));
+
+    list.retain(|_| false);
+    list.shrink_to_fit();
+    assert_eq!(list.capacity(), 0);
+    list.push_back(1);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1]);
+}
+
+#[test]
+fn test_drain_filter() {
+    let mut list: List<_> = (0..10).collect();
+    let nine = list.find(|&value| value == 9).unwrap();
+
+    let evens: Vec<_> = list.drain_filter(|value| *value % 2 == 0).collect();
+    assert_eq!(evens, [0, 2, 4, 6, 8]);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &3, &5, &7, &9]);
+    assert_eq!(list.get(nine), Some(&9));
+
+    let mut drain = list.drain_filter(|value| {
+        *value *= 10;
+        *value > 20
+    });
+    assert_eq!(drain.next(), Some(30));
+    drop(drain);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&10], "Dropping drains the rest");
+    assert_eq!(list.peek_back(), Some(&10));
+}
+
+#[test]
+fn test_insert_at_handle() {
+    let mut list = List::new();
+    let two = list.push_back(2);
+
+    let four = list.insert_after(two, 4).unwrap();
+    let one = list.insert_before(two, 1).unwrap();
+    let three = list.insert_before(four, 3).unwrap();
+    list.insert_after(four, 5).unwrap();
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &3, &4, &5]);
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), [&5, &4, &3, &2, &1]);
+    assert_eq!(list.get(three), Some(&3));
+
+    list.remove(one);
+    assert_eq!(list.insert_after(one, 0), None);
+    assert_eq!(list.insert_before(one, 0), None);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.peek_front(), Some(&2));
+}
+
+#[test]
+fn test_rotate() {
+    let mut list: List<_> = (0..5).collect();
+    let values = |list: &List<i32>| list.iter().copied().collect::<Vec<_>>();
+
+    list.rotate_forward(1);
+    assert_eq!(values(&list), [1, 2, 3, 4, 0]);
+    list.rotate_forward(4);
+    assert_eq!(values(&list), [0, 1, 2, 3, 4]);
+    list.rotate_forward(12);
+    assert_eq!(values(&list), [2, 3, 4, 0, 1]);
+    list.rotate_backward(2);
+    assert_eq!(values(&list), [0, 1, 2, 3, 4]);
+    list.rotate_backward(1);
+    assert_eq!(values(&list), [4, 0, 1, 2, 3]);
+    assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), [3, 2, 1, 0, 4]);
+
+    list.rotate_forward(5);
+    assert_eq!(values(&list), [4, 0, 1, 2, 3]);
+    List::<i32>::new().rotate_forward(3);
+}
+
+#[test]
+fn test_move_to_ends() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    let two = list.push_back(2);
+    let three = list.push_back(3);
+
+    assert!(list.move_to_back(one));
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&2, &3, &1]);
+    assert!(list.move_to_front(three));
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+    assert!(list.move_to_front(three));
+    assert!(list.move_to_back(one));
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), [&1, &2, &3]);
+    assert_eq!(list.get(one), Some(&1));
+
+    list.remove(two);
+    assert!(!list.move_to_back(two));
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &1]);
+}
+
+#[test]
+fn test_reverse() {
+    let mut list: List<_> = (1..5).collect();
+    let two = list.find(|&value| value == 2).unwrap();
+    assert_eq!(list.rev_iter().collect::<Vec<_>>(), [&4, &3, &2, &1]);
+
+    list.reverse();
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&4, &3, &2, &1]);
+    assert_eq!(list.rev_iter().collect::<Vec<_>>(), [&1, &2, &3, &4]);
+    assert_eq!((list.peek_front(), list.peek_back()), (Some(&4), Some(&1)));
+    assert_eq!(list.get(two), Some(&2));
+
+    list.push_back(0);
+    assert_eq!(list.pop_front(), Some(4));
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1, &0]);
+
+    let mut empty = List::<i32>::new();
+    empty.reverse();
+    assert!(empty.rev_iter().next().is_none());
+}
+
+#[test]
+fn test_unlink_relink() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    let two = list.push_back(2);
+    let three = list.push_back(3);
+
+    assert!(list.unlink(two));
+    assert!(!list.unlink(two), "Already unlinked");
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &3]);
+    assert_eq!(list.get(two), Some(&2));
+    assert_eq!(list.insert_after(two, 0), None);
+    assert!(!list.move_to_front(two));
+
+    assert!(list.relink_front(two));
+    assert!(!list.relink_front(two), "Already linked");
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&2, &1, &3]);
+
+    assert!(list.unlink(two));
+    assert!(list.unlink(three));
+    assert!(list.unlink(one));
+    assert!(list.is_empty());
+    assert_eq!((list.peek_front(), list.peek_back()), (None, None));
+
+    assert!(list.relink_back(three));
+    assert!(list.relink_back(one));
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), [&1, &3]);
+
+    assert_eq!(list.remove(two), Some(2), "Unlinked nodes can be removed");
+    assert_eq!(list.len(), 2);
+    assert!(!list.relink_back(two));
+}
+
+#[test]
+fn test_unlinked_nodes_stay() {
+    let mut list: List<_> = (0..3).collect();
+    let zero = list.find(|&value| value == 0).unwrap();
+    list.unlink(zero);
+
+    let rest = list.split_off(list.find(|&value| value == 1).unwrap());
+    assert_eq!(rest.iter().collect::<Vec<_>>(), [&1, &2]);
+    assert!(list.is_empty());
+    assert_eq!(list.get(zero), Some(&0), "Unlinked nodes aren't split off");
+
+    list.shrink_to_fit();
+    assert!(list.relink_back(zero));
+    assert_eq!(list.iter().collect::<Vec<_>>(), [&0]);
+}
+
+#[test]
+fn test_to_vec() {
+    let mut list = List::new();
+    assert_eq!(list.to_vec(), Vec::<String>::new());
+
+    list.push_back("b".to_string());
+    list.push_front("a".to_string());
+    let c = list.push_back("c".to_string());
+    list.unlink(c);
+
+    assert_eq!(list.to_vec(), ["a", "b"]);
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.into_vec(), ["a", "b"]);
+}
+
+#[test]
+fn test_index() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    let two = list.push_back(2);
+
+    assert_eq!(list[one], 1);
+    list[two] += 10;
+    *list.get_mut(one).unwrap() *= 5;
+    assert_eq!(list.to_vec(), [5, 12]);
+
+    list.remove(one);
+    assert_eq!(list.get_mut(one), None);
+}
+
+#[test]
+#[should_panic(expected = "no node for handle")]
+fn test_index_removed() {
+    let mut list = List::new();
+    let one = list.push_back(1);
+    list.remove(one);
+
+    let _ = list[one];
+}
+
+#[test]
+fn test_split_at_head_with_unlinked() {
+    let mut list = List::new();
+    let a = list.push_back(1);
+    let b = list.push_back(2);
+    list.push_back(3);
+    list.unlink(b);
+
+    let rest = list.split_off(a);
+    assert_eq!(rest.to_vec(), [1, 3]);
+    assert!(list.is_empty());
+    assert_eq!(list.peek_front(), None);
+    assert!(list.cursor_front_mut().current().is_none());
+
+    list.push_front(0);
+    assert!(list.relink_back(b));
+    assert_eq!(list.to_vec(), [0, 2]);
+    assert_eq!(list.pop_front(), Some(0));
+    assert_eq!(list.pop_front(), Some(2));
+    assert_eq!(list.pop_front(), None);
+}
+
+#[test]
+fn test_sort_panic_leaves_list_intact() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    for panic_at in 1..10 {
+        let mut list: List<_> = vec![5, 3, 9, 1, 7, 2].into();
+        let mut comparisons = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                comparisons += 1;
+                if comparisons == panic_at {
+                    panic!("comparator failed");
+                }
+                a.cmp(b)
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(list.to_vec(), [5, 3, 9, 1, 7, 2]);
+        assert_eq!(list.rev_iter().copied().collect::<Vec<_>>(), [2, 7, 1, 9, 3, 5]);
+    }
+
+    let mut list: List<_> = vec![5, 3, 9, 1, 7, 2].into();
+    assert_eq!(list.pop_front(), Some(5));
+    list.sort();
+    assert_eq!(list.to_vec(), [1, 2, 3, 7, 9]);
+}
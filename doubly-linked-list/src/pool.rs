@@ -0,0 +1,511 @@
+use slab::Slab;
+use std::ops::{Index, IndexMut};
+use std::sync::atomic::Ordering;
+
+use crate::{Node, NodeHandle, NEXT_GENERATION, NULL};
+
+/// Several doubly-linked lists whose nodes all live in one shared `Slab`,
+/// so nodes move from one list to another by relinking alone, without
+/// moving their values. Appending a list to another takes O(1) time, and
+/// handles keep resolving wherever their nodes move within the pool.
+///
+/// Lists are referred to by the `ListId` returned when they are created,
+/// and methods taking a `ListId` panic if the list was removed. A node
+/// doesn't record which list it is in, so passing a handle along with a
+/// list it isn't in is a logic error. It is caught when the node is at
+/// either end of a list, and otherwise leaves the lengths of the lists
+/// involved wrong, but it can't cause undefined behavior.
+pub struct ListPool<T> {
+    nodes: Slab<Node<T>>,
+    lists: Slab<Ends>,
+}
+
+/// A reference to a list of a `ListPool`. Like a `NodeHandle`, it stops
+/// resolving once its list is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListId {
+    index: usize,
+    generation: u64,
+}
+
+/// The ends and length of one list of a pool
+struct Ends {
+    head: usize,
+    tail: usize,
+    len: usize,
+    generation: u64,
+}
+
+/// An iterator over references to the values of one list of a
+/// `ListPool`, from front to back.
+pub struct PoolIter<'a, T> {
+    pool: &'a ListPool<T>,
+    next: usize,
+    remaining: usize,
+}
+
+impl<T> Default for ListPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ListPool<T> {
+    /// Create a pool with no lists
+    pub fn new() -> Self {
+        ListPool {
+            nodes: Slab::new(),
+            lists: Slab::new(),
+        }
+    }
+
+    /// Create an empty list in the pool
+    pub fn new_list(&mut self) -> ListId {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        let index = self.lists.insert(Ends { head: NULL, tail: NULL, len: 0, generation });
+
+        ListId { index, generation }
+    }
+
+    /// Remove a list from the pool, dropping its values. Returns false if
+    /// it was already removed
+    pub fn remove_list(&mut self, list: ListId) -> bool {
+        if !self.contains_list(list) {
+            return false;
+        }
+
+        let ends = self.lists.remove(list.index);
+        let mut index = ends.head;
+        while index != NULL {
+            index = self.nodes.remove(index).next;
+        }
+
+        true
+    }
+
+    /// Return whether the list hasn't been removed
+    pub fn contains_list(&self, list: ListId) -> bool {
+        matches!(self.lists.get(list.index), Some(ends) if ends.generation == list.generation)
+    }
+
+    /// Return the number of values in the list
+    pub fn len(&self, list: ListId) -> usize {
+        self.ends(list).len
+    }
+
+    pub fn is_empty(&self, list: ListId) -> bool {
+        self.len(list) == 0
+    }
+
+    fn ends(&self, list: ListId) -> &Ends {
+        assert!(self.contains_list(list), "no list for id");
+        &self.lists[list.index]
+    }
+
+    fn ends_mut(&mut self, list: ListId) -> &mut Ends {
+        assert!(self.contains_list(list), "no list for id");
+        &mut self.lists[list.index]
+    }
+
+    /// Link two nodes together such that `a.next` is `b` and `b.prev` is `a`
+    fn link(&mut self, a: usize, b: usize) {
+        if a != NULL { self.nodes[a].next = b; }
+        if b != NULL { self.nodes[b].prev = a; }
+    }
+
+    /// Link the run of `len` nodes from `first` to `last` into a list,
+    /// between two adjacent nodes of it, either of which can be `NULL` at
+    /// the ends of the list
+    fn link_run(&mut self, list: ListId, prev: usize, next: usize, (first, last, len): (usize, usize, usize)) {
+        self.link(prev, first);
+        self.link(last, next);
+
+        let ends = self.ends_mut(list);
+        if prev == NULL { ends.head = first; }
+        if next == NULL { ends.tail = last; }
+        ends.len += len;
+    }
+
+    /// Take the run of `len` nodes from `first` to `last` out of a list,
+    /// linking the nodes around it to each other
+    fn unlink_run(&mut self, list: ListId, (first, last, len): (usize, usize, usize)) {
+        let (prev, next) = (self.nodes[first].prev, self.nodes[last].next);
+        self.link(prev, next);
+        self.nodes[first].prev = NULL;
+        self.nodes[last].next = NULL;
+
+        let ends = self.ends_mut(list);
+        if prev == NULL { ends.head = next; }
+        if next == NULL { ends.tail = prev; }
+        ends.len -= len;
+    }
+
+    /// Return the index of the node a handle refers to, if it is still
+    /// in the pool
+    fn handle_index(&self, handle: NodeHandle) -> Option<usize> {
+        match self.nodes.get(handle.index) {
+            Some(node) if node.generation == handle.generation => Some(handle.index),
+            _ => None,
+        }
+    }
+
+    /// Return the index of the node a handle refers to, if it is still in
+    /// the pool and can be in the list: a node with no previous node must
+    /// be the head of the list, and one with no next node its tail
+    fn index_in(&self, list: ListId, handle: NodeHandle) -> Option<usize> {
+        let index = self.handle_index(handle)?;
+        let (node, ends) = (&self.nodes[index], self.ends(list));
+
+        if (node.prev == NULL) != (ends.head == index) || (node.next == NULL) != (ends.tail == index) {
+            None
+        } else {
+            Some(index)
+        }
+    }
+
+    /// Add a value to the back of the list, and return a handle to it
+    pub fn push_back(&mut self, list: ListId, value: T) -> NodeHandle {
+        let tail = self.ends(list).tail;
+        let handle = self.insert(value);
+        self.link_run(list, tail, NULL, (handle.index, handle.index, 1));
+
+        handle
+    }
+
+    /// Add a value to the front of the list, and return a handle to it
+    pub fn push_front(&mut self, list: ListId, value: T) -> NodeHandle {
+        let head = self.ends(list).head;
+        let handle = self.insert(value);
+        self.link_run(list, NULL, head, (handle.index, handle.index, 1));
+
+        handle
+    }
+
+    /// Put a value in a node of its own, not linked into any list
+    fn insert(&mut self, value: T) -> NodeHandle {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        let index = self.nodes.insert(Node { value, prev: NULL, next: NULL, generation });
+
+        NodeHandle { index, generation }
+    }
+
+    /// Remove the value at the front of the list and return it
+    pub fn pop_front(&mut self, list: ListId) -> Option<T> {
+        let head = self.ends(list).head;
+        self.remove_index(list, head)
+    }
+
+    /// Remove the value at the back of the list and return it
+    pub fn pop_back(&mut self, list: ListId) -> Option<T> {
+        let tail = self.ends(list).tail;
+        self.remove_index(list, tail)
+    }
+
+    /// Remove the node the handle refers to from the list and return its
+    /// value, or `None` if it was already removed
+    pub fn remove(&mut self, list: ListId, handle: NodeHandle) -> Option<T> {
+        let index = self.index_in(list, handle)?;
+        self.remove_index(list, index)
+    }
+
+    /// Remove the node at `index`, which can be `NULL`, from the list
+    fn remove_index(&mut self, list: ListId, index: usize) -> Option<T> {
+        if index == NULL {
+            return None;
+        }
+
+        self.unlink_run(list, (index, index, 1));
+        Some(self.nodes.remove(index).value)
+    }
+
+    /// Return a reference to the value of the node the handle refers to,
+    /// whichever list it is in, or `None` if it was removed
+    pub fn get(&self, handle: NodeHandle) -> Option<&T> {
+        let index = self.handle_index(handle)?;
+        Some(&self.nodes[index].value)
+    }
+
+    /// Return a mutable reference to the value of the node the handle
+    /// refers to, like `get`
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        let index = self.handle_index(handle)?;
+        Some(&mut self.nodes[index].value)
+    }
+
+    /// Move all the values of `other` to the back of `list` in O(1) time,
+    /// leaving `other` empty. Handles to the moved nodes stay valid
+    pub fn append(&mut self, list: ListId, other: ListId) {
+        assert_ne!(list, other, "can't append a list to itself");
+
+        let ends = self.ends_mut(other);
+        let run = (ends.head, ends.tail, ends.len);
+        if run.2 == 0 {
+            return;
+        }
+        ends.head = NULL;
+        ends.tail = NULL;
+        ends.len = 0;
+
+        let tail = self.ends(list).tail;
+        self.link_run(list, tail, NULL, run);
+    }
+
+    /// Split the list in two at the given node, moving that node and
+    /// every node after it to a new list, whose id is returned. Nodes are
+    /// only relinked, so all handles stay valid. Counting the nodes takes
+    /// O(min(k, len - k)) time, where k is the number of nodes moved.
+    /// Panics if the node isn't in the list
+    pub fn split_off(&mut self, list: ListId, at: NodeHandle) -> ListId {
+        let at = self.index_in(list, at).expect("split_off at a node not in the list");
+
+        // walk towards both ends at once, so the nearer one is found
+        // first, and tells how many nodes are moved
+        let (mut forward, mut backward) = (at, self.nodes[at].prev);
+        let mut steps = 0;
+        let moved = loop {
+            if forward == NULL {
+                break steps;
+            }
+            if backward == NULL {
+                break self.len(list) - steps;
+            }
+            forward = self.nodes[forward].next;
+            backward = self.nodes[backward].prev;
+            steps += 1;
+        };
+
+        let run = (at, self.ends(list).tail, moved);
+        self.unlink_run(list, run);
+        let other = self.new_list();
+        self.link_run(other, NULL, NULL, run);
+
+        other
+    }
+
+    /// Move the run of nodes from `first` to `last` inclusive out of
+    /// `from` and into `to`, which can be the same list, keeping their
+    /// order, before the node `at` or at the back of `to` if `at` is
+    /// `None`. Pass the same handle as `first` and `last` to move a single
+    /// node.
+    ///
+    /// Nodes are only relinked, so handles to them stay valid. Moving a
+    /// single node takes O(1) time, and a run of k nodes O(k) time, spent
+    /// counting them. Returns false and moves nothing if a handle isn't in
+    /// its list, `last` doesn't come after `first`, or `at` is in the run
+    pub fn move_run(
+        &mut self,
+        from: ListId,
+        first: NodeHandle,
+        last: NodeHandle,
+        to: ListId,
+        at: Option<NodeHandle>,
+    ) -> bool {
+        let (first, last) = match (self.index_in(from, first), self.index_in(from, last)) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return false,
+        };
+        let next = match at {
+            Some(at) => match self.index_in(to, at) {
+                Some(next) => next,
+                None => return false,
+            },
+            None => NULL,
+        };
+
+        let mut len = 1;
+        let mut index = first;
+        while index != last {
+            if index == next {
+                return false;
+            }
+            index = self.nodes[index].next;
+            if index == NULL {
+                return false;
+            }
+            len += 1;
+        }
+        if index == next {
+            return false;
+        }
+
+        self.unlink_run(from, (first, last, len));
+        let prev = if next == NULL { self.ends(to).tail } else { self.nodes[next].prev };
+        self.link_run(to, prev, next, (first, last, len));
+
+        true
+    }
+
+    /// Return an iterator over the values of the list, from front to back
+    pub fn iter(&self, list: ListId) -> PoolIter<'_, T> {
+        let ends = self.ends(list);
+        PoolIter {
+            pool: self,
+            next: ends.head,
+            remaining: ends.len,
+        }
+    }
+}
+
+/// Panics if the node the handle refers to was removed
+impl<T> Index<NodeHandle> for ListPool<T> {
+    type Output = T;
+
+    fn index(&self, handle: NodeHandle) -> &T {
+        self.get(handle).expect("no node for handle")
+    }
+}
+
+/// Panics if the node the handle refers to was removed
+impl<T> IndexMut<NodeHandle> for ListPool<T> {
+    fn index_mut(&mut self, handle: NodeHandle) -> &mut T {
+        self.get_mut(handle).expect("no node for handle")
+    }
+}
+
+impl<'a, T> Iterator for PoolIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = &self.pool.nodes[self.next];
+        self.next = node.next;
+        self.remaining -= 1;
+
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for PoolIter<'_, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn values(pool: &ListPool<i32>, list: ListId) -> Vec<i32> {
+        pool.iter(list).copied().collect()
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut pool = ListPool::new();
+        let list = pool.new_list();
+        let other = pool.new_list();
+        assert!(pool.is_empty(list));
+
+        let two = pool.push_back(list, 2);
+        let one = pool.push_front(list, 1);
+        pool.push_back(list, 3);
+        pool.push_back(other, 10);
+        assert_eq!(values(&pool, list), [1, 2, 3]);
+        assert_eq!(pool.len(other), 1);
+
+        assert_eq!(pool.remove(other, one), None, "Node isn't in the other list");
+        assert_eq!(pool.remove(list, two), Some(2));
+        assert_eq!(pool.get(two), None);
+        assert_eq!(pool.pop_back(list), Some(3));
+        assert_eq!(pool.pop_front(list), Some(1));
+        assert_eq!(pool.pop_front(list), None);
+
+        assert!(pool.remove_list(other));
+        assert!(!pool.contains_list(other));
+        assert!(!pool.remove_list(other));
+    }
+
+    #[test]
+    fn test_append() {
+        let mut pool = ListPool::new();
+        let list = pool.new_list();
+        let other = pool.new_list();
+        pool.push_back(list, 1);
+        let two = pool.push_back(other, 2);
+        pool.push_back(other, 3);
+
+        pool.append(list, other);
+        assert_eq!(values(&pool, list), [1, 2, 3]);
+        assert!(pool.is_empty(other));
+        assert_eq!(pool[two], 2, "Handles stay valid");
+
+        pool.push_back(other, 4);
+        pool.append(list, other);
+        pool.append(list, other);
+        assert_eq!(values(&pool, list), [1, 2, 3, 4]);
+        assert_eq!(pool.remove(list, two), Some(2));
+
+        let empty = pool.new_list();
+        pool.append(empty, list);
+        assert_eq!(values(&pool, empty), [1, 3, 4]);
+        assert_eq!(pool.len(list), 0);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut pool = ListPool::new();
+        let list = pool.new_list();
+        let handles: Vec<_> = (0..6).map(|value| pool.push_back(list, value)).collect();
+
+        let back = pool.split_off(list, handles[4]);
+        assert_eq!(values(&pool, list), [0, 1, 2, 3]);
+        assert_eq!(values(&pool, back), [4, 5]);
+
+        let middle = pool.split_off(list, handles[1]);
+        assert_eq!((pool.len(list), pool.len(middle)), (1, 3));
+        assert_eq!(pool.remove(middle, handles[3]), Some(3), "Handles stay valid");
+
+        let all = pool.split_off(list, handles[0]);
+        assert!(pool.is_empty(list));
+        assert_eq!(values(&pool, all), [0]);
+
+        pool.append(all, middle);
+        pool.append(all, back);
+        assert_eq!(values(&pool, all), [0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_move_run() {
+        let mut pool = ListPool::new();
+        let list = pool.new_list();
+        let target = pool.new_list();
+        let handles: Vec<_> = (0..5).map(|value| pool.push_back(list, value)).collect();
+        pool.push_back(target, 10);
+        let eleven = pool.push_back(target, 11);
+
+        assert!(pool.move_run(list, handles[1], handles[3], target, Some(eleven)));
+        assert_eq!(values(&pool, list), [0, 4]);
+        assert_eq!(values(&pool, target), [10, 1, 2, 3, 11]);
+        assert_eq!(pool[handles[2]], 2, "Handles stay valid");
+
+        assert!(pool.move_run(list, handles[4], handles[4], target, None));
+        assert_eq!(values(&pool, target), [10, 1, 2, 3, 11, 4]);
+        assert_eq!(values(&pool, list), [0]);
+
+        assert!(!pool.move_run(target, handles[3], handles[1], list, None), "Last before first");
+        assert!(!pool.move_run(target, handles[1], handles[3], target, Some(handles[2])), "At in the run");
+        assert!(!pool.move_run(target, handles[3], handles[3], list, Some(handles[4])), "At not in list");
+        assert_eq!(pool.len(target), 6);
+
+        assert!(pool.move_run(target, handles[1], handles[2], target, None));
+        assert_eq!(values(&pool, target), [10, 3, 11, 4, 1, 2]);
+        assert!(pool.remove(target, handles[2]).is_some());
+
+        assert!(pool.move_run(list, handles[0], handles[0], target, Some(handles[3])));
+        assert!(pool.is_empty(list));
+        assert_eq!(values(&pool, target), [10, 0, 3, 11, 4, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "split_off at a node not in the list")]
+    fn test_split_off_other_list() {
+        let mut pool = ListPool::new();
+        let list = pool.new_list();
+        let other = pool.new_list();
+        let one = pool.push_back(other, 1);
+        pool.split_off(list, one);
+    }
+}
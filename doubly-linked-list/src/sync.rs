@@ -0,0 +1,177 @@
+use crate::{List, NodeHandle};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+/// A `List` that can be shared between threads, for example as the queue
+/// of a multi-threaded work pipeline. Every operation locks the whole
+/// list, so pushes and pops at both ends are linearizable, and `lock`
+/// gives exclusive access for anything that needs several steps.
+pub struct SyncList<T> {
+    list: Mutex<List<T>>,
+    /// Signalled whenever a value is pushed, to wake up `wait_pop_front`
+    pushed: Condvar,
+}
+
+impl<T> Default for SyncList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<List<T>> for SyncList<T> {
+    fn from(list: List<T>) -> Self {
+        SyncList {
+            list: Mutex::new(list),
+            pushed: Condvar::new(),
+        }
+    }
+}
+
+impl<T> SyncList<T> {
+    /// Create an empty list
+    pub fn new() -> Self {
+        List::new().into()
+    }
+
+    /// Add a value to the back of the list, and return a handle to it
+    pub fn push_back(&self, value: T) -> NodeHandle {
+        let handle = self.lock().push_back(value);
+        self.pushed.notify_one();
+
+        handle
+    }
+
+    /// Add a value to the front of the list, and return a handle to it
+    pub fn push_front(&self, value: T) -> NodeHandle {
+        let handle = self.lock().push_front(value);
+        self.pushed.notify_one();
+
+        handle
+    }
+
+    /// Remove the value at the back of the list and return it
+    pub fn pop_back(&self) -> Option<T> {
+        self.lock().pop_back()
+    }
+
+    /// Remove the value at the front of the list and return it
+    pub fn pop_front(&self) -> Option<T> {
+        self.lock().pop_front()
+    }
+
+    /// Remove the value at the front of the list and return it, waiting
+    /// for another thread to push one if the list is empty
+    pub fn wait_pop_front(&self) -> T {
+        let mut list = self.lock();
+        loop {
+            if let Some(value) = list.pop_front() {
+                return value;
+            }
+            list = self.pushed.wait(list).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Remove the node the handle refers to and return its value, or
+    /// `None` if it was already removed
+    pub fn remove(&self, handle: NodeHandle) -> Option<T> {
+        self.lock().remove(handle)
+    }
+
+    /// Return the number of values in the list
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Lock the list, blocking other threads until the guard is dropped.
+    /// Values pushed through the guard don't wake up `wait_pop_front`
+    ///
+    /// Every `List` operation keeps the list linked consistently even
+    /// when a closure it calls panics, such as the comparator of
+    /// `sort_by`, so a poisoned lock is recovered rather than propagated
+    pub fn lock(&self) -> MutexGuard<'_, List<T>> {
+        self.list.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Consume the lock and return the list inside it
+    pub fn into_inner(self) -> List<T> {
+        self.list.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let list = SyncList::new();
+        assert!(list.is_empty());
+
+        list.push_back(2);
+        list.push_front(1);
+        let three = list.push_back(3);
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(list.remove(three), Some(3));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), None);
+
+        list.lock().extend(vec![4, 5]);
+        assert_eq!(list.into_inner().into_vec(), [4, 5]);
+    }
+
+    #[test]
+    fn test_recover_from_panic() {
+        let list = Arc::new(SyncList::new());
+        list.lock().extend(vec![3, 1, 2]);
+
+        let sorter = Arc::clone(&list);
+        let result = thread::spawn(move || sorter.lock().sort_by(|_, _| panic!("comparator failed"))).join();
+        assert!(result.is_err());
+
+        assert_eq!(list.lock().to_vec(), [3, 1, 2]);
+        assert_eq!(list.pop_front(), Some(3));
+        list.push_back(4);
+        assert_eq!(list.pop_back(), Some(4));
+    }
+
+    #[test]
+    fn test_threads() {
+        let list = Arc::new(SyncList::new());
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let list = Arc::clone(&list);
+                thread::spawn(move || (0..100).map(|_| list.wait_pop_front()).sum::<u64>())
+            })
+            .collect();
+        let producers: Vec<_> = (0..4)
+            .map(|t| {
+                let list = Arc::clone(&list);
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        if i % 2 == 0 {
+                            list.push_back(t * 100 + i);
+                        } else {
+                            list.push_front(t * 100 + i);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let total: u64 = consumers.into_iter().map(|consumer| consumer.join().unwrap()).sum();
+
+        assert_eq!(total, (0..400).sum());
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use core::fmt;
+
+/// A fixed-capacity cache holding up to `N` values of type `T`, using the
+/// CLOCK (second-chance) approximation of LRU.
+/// Every slot carries a reference bit that a hit sets. On eviction, a hand
+/// sweeps around the slots, clearing set bits, and replaces the first slot
+/// whose bit is already clear. Touching an entry never moves it, so hits
+/// cost a single bit write instead of relinking a list.
+pub struct ClockCache<T, const N: usize> {
+    /// Slots are filled in index order and are never vacated, except by
+    /// `clear`.
+    slots: [Slot<T>; N],
+    /// Index of the next slot the hand will inspect on eviction.
+    hand: usize,
+    /// Number of entries in the cache.
+    length: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    /// The value stored in this slot, or `None` if the slot is unused
+    val: Option<T>,
+    /// Whether the value was touched since the hand last passed it
+    referenced: bool,
+}
+
+impl<T, const N: usize> Default for ClockCache<T, N> {
+    fn default() -> Self {
+        assert!(N > 0, "ClockCache needs room for at least one entry");
+
+        ClockCache {
+            slots: core::array::from_fn(|_| Slot {
+                val: None,
+                referenced: false,
+            }),
+            hand: 0,
+            length: 0,
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ClockCache<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            hand: self.hand,
+            length: self.length,
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ClockCache<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClockCache")
+            .field("hand", &self.hand)
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+impl<T, const N: usize> ClockCache<T, N> {
+    /// Returns the maximum number of elements the cache can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the cache
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Performs a lookup on the cache with the given predicate.
+    /// Marks the result as referenced on a hit.
+    pub fn lookup<F, R>(&mut self, mut pred: F) -> Option<R>
+    where
+        F: FnMut(&mut T) -> Option<R>,
+    {
+        for slot in self.slots[..self.length].iter_mut() {
+            if let Some(r) = slot.val.as_mut().and_then(&mut pred) {
+                slot.referenced = true;
+                return Some(r);
+            }
+        }
+
+        None
+    }
+
+    /// Marks the first item in the cache that matches the given
+    /// predicate as referenced. Returns `true` on a hit, `false` if no
+    /// matches.
+    pub fn touch<F>(&mut self, pred: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.fetch(pred).is_some()
+    }
+
+    /// Returns the first item in the cache that matches the given
+    /// predicate, marking it as referenced.
+    pub fn fetch<F>(&mut self, mut pred: F) -> Option<&mut T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let slot = self.slots[..self.length]
+            .iter_mut()
+            .find(|slot| slot.val.as_ref().is_some_and(&mut pred))?;
+        slot.referenced = true;
+
+        slot.val.as_mut()
+    }
+
+    /// Insert a given value in the cache.
+    /// If the cache is full, the hand sweeps forward to pick an entry
+    /// that hasn't been referenced since its last pass, and the new
+    /// value replaces it. New entries start out unreferenced.
+    pub fn insert(&mut self, val: T) {
+        let index = if self.length < N {
+            self.length += 1;
+            self.length - 1
+        } else {
+            while self.slots[self.hand].referenced {
+                self.slots[self.hand].referenced = false;
+                self.advance_hand();
+            }
+
+            let victim = self.hand;
+            self.advance_hand();
+            victim
+        };
+
+        self.slots[index] = Slot {
+            val: Some(val),
+            referenced: false,
+        };
+    }
+
+    /// Clear all entries from the cache.
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.val = None;
+            slot.referenced = false;
+        }
+
+        self.hand = 0;
+        self.length = 0;
+    }
+
+    /// Iterate over the contents of the cache, in slot order. The order
+    /// says nothing about how recently entries were used.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.slots[..self.length]
+            .iter()
+            .filter_map(|slot| slot.val.as_ref())
+    }
+
+    /// Moves the hand to the next slot, wrapping around at the end.
+    #[inline]
+    fn advance_hand(&mut self) {
+        self.hand = (self.hand + 1) % N;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type TestCache = ClockCache<i32, 4>;
+
+    /// Convenience function for test assertions
+    fn items(cache: &TestCache) -> Vec<i32> {
+        let mut items: Vec<_> = cache.iter().copied().collect();
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut cache = TestCache::default();
+        assert!(cache.is_empty());
+
+        for i in 1..=4 {
+            cache.insert(i);
+        }
+        assert_eq!(cache.len(), 4);
+        assert_eq!(items(&cache), [1, 2, 3, 4]);
+
+        cache.insert(5);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(items(&cache), [2, 3, 4, 5], "Oldest unreferenced item evicted");
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(items(&cache), []);
+    }
+
+    #[test]
+    fn test_second_chance() {
+        let mut cache = TestCache::default();
+        for i in 1..=4 {
+            cache.insert(i);
+        }
+
+        assert!(cache.touch(|x| *x == 1));
+        assert!(cache.touch(|x| *x == 2));
+        assert!(!cache.touch(|x| *x == 7));
+
+        cache.insert(5);
+        assert_eq!(items(&cache), [1, 2, 4, 5], "Referenced items skipped");
+
+        // the hand cleared the bits on 1 and 2 as it passed them
+        cache.insert(6);
+        assert_eq!(items(&cache), [1, 2, 5, 6]);
+        cache.insert(7);
+        assert_eq!(items(&cache), [2, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_lookup_fetch() {
+        let mut cache = TestCache::default();
+        for i in 1..=4 {
+            cache.insert(i);
+        }
+
+        assert_eq!(cache.lookup(|x| if *x == 3 { Some(*x * 2) } else { None }), Some(6));
+        assert_eq!(cache.lookup(|x| if *x == 9 { Some(()) } else { None }), None);
+
+        *cache.fetch(|x| *x == 4).unwrap() = 40;
+        assert_eq!(items(&cache), [1, 2, 3, 40]);
+
+        cache.insert(5);
+        cache.insert(6);
+        assert_eq!(items(&cache), [3, 5, 6, 40], "Fetched and looked-up items kept");
+    }
+}
@@ -0,0 +1,148 @@
+use crate::LRUCache;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A single shard of a `ConcurrentLru`.
+type Shard<K, V, const N: usize> = Mutex<LRUCache<(K, V), N>>;
+
+/// A thread-safe LRU cache mapping keys to values.
+/// Keys are spread over a number of shards by hash, and each shard is
+/// an independent `LRUCache` behind its own mutex, so threads working
+/// on different shards never contend. Each shard holds up to `N`
+/// entries, and recency is only tracked within a shard.
+pub struct ConcurrentLru<K, V, const N: usize> {
+    shards: Box<[Shard<K, V, N>]>,
+    hasher: RandomState,
+}
+
+impl<K, V, const N: usize> ConcurrentLru<K, V, N>
+where
+    K: Hash + Eq,
+{
+    /// Creates a new cache split into the given number of shards.
+    pub fn new(shards: usize) -> Self {
+        assert!(shards > 0, "ConcurrentLru needs at least one shard");
+
+        ConcurrentLru {
+            shards: (0..shards).map(|_| Mutex::new(LRUCache::default())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns a clone of the value stored for `key`, touching it on a hit.
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.lock(key)
+            .lookup(|(k, v)| if k == key { Some(v.clone()) } else { None })
+    }
+
+    /// Stores `value` for `key`, replacing and touching any existing
+    /// entry. If the key's shard is full, its least-recently-used entry
+    /// is evicted.
+    pub fn put(&self, key: K, value: V) {
+        let mut shard = self.lock(&key);
+
+        match shard.fetch(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => {
+                shard.insert((key, value));
+            },
+        }
+    }
+
+    /// Returns the total number of entries across all shards.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| Self::lock_shard(s).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| Self::lock_shard(s).is_empty())
+    }
+
+    /// Clear all entries from every shard.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            Self::lock_shard(shard).clear();
+        }
+    }
+
+    /// Locks the shard responsible for the given key.
+    fn lock(&self, key: &K) -> MutexGuard<'_, LRUCache<(K, V), N>> {
+        let index = (self.hasher.hash_one(key) % self.shards.len() as u64) as usize;
+
+        Self::lock_shard(&self.shards[index])
+    }
+
+    /// Locks a shard, recovering a poisoned lock rather than propagating
+    /// it, so one panicking thread doesn't make a shard unusable. The
+    /// shard stays memory safe, but a panic partway through an operation,
+    /// such as a value's `Drop` panicking while `insert_weighted` evicts
+    /// it, can leave a slot unlinked and unfree and the shard's counts
+    /// off, so the shard holds fewer entries from then on.
+    fn lock_shard(shard: &Shard<K, V, N>) -> MutexGuard<'_, LRUCache<(K, V), N>> {
+        shard.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    type TestCache = ConcurrentLru<u32, u32, 4>;
+
+    #[test]
+    fn test_get_put() {
+        let cache = TestCache::new(1);
+        assert!(cache.is_empty());
+
+        cache.put(1, 10);
+        cache.put(2, 20);
+        assert_eq!(cache.get(&1), Some(10));
+        assert_eq!(cache.get(&3), None);
+
+        cache.put(1, 11);
+        assert_eq!(cache.get(&1), Some(11), "Value replaced.");
+        assert_eq!(cache.len(), 2);
+
+        cache.put(3, 30);
+        cache.put(4, 40);
+        cache.put(5, 50);
+        assert_eq!(cache.get(&2), None, "Least-recently-used item evicted");
+        assert_eq!(cache.get(&1), Some(11));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_threads() {
+        let cache = Arc::new(TestCache::new(8));
+
+        let workers: Vec<_> = (0..4)
+            .map(|t| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let key = t * 100 + i;
+                        cache.put(key, key * 2);
+                        // another thread may have evicted the key already
+                        if let Some(value) = cache.get(&key) {
+                            assert_eq!(value, key * 2);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert!(cache.len() <= 8 * 4);
+    }
+}
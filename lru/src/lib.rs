@@ -1,4 +1,3 @@
-use arrayvec::{Array, ArrayVec};
 use core::fmt;
 
 #[cfg(test)]
@@ -8,67 +7,126 @@ extern crate quickcheck;
 #[macro_use(quickcheck)]
 extern crate quickcheck_macros;
 
-pub struct LRUCache<A: Array> {
+mod clock;
+mod concurrent;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod slru;
+
+pub use clock::ClockCache;
+pub use concurrent::ConcurrentLru;
+pub use slru::SlruCache;
+
+/// A fixed-capacity cache holding up to `N` values of type `T`.
+pub struct LRUCache<T, const N: usize> {
     /// The most-recently-used entry is located at the `head` index
     /// These entries form a linked list. Once an entry is added to
     /// the array, its index never changes.
-    entries: ArrayVec<A>,
+    entries: [Entry<T>; N],
     /// Index of the first entry in the cache.
     head: usize,
     /// Index of the last entry in the cache.
     tail: usize,
     /// Number of entries in the cache.
     length: usize,
+    /// Index of the first vacant slot. Vacant slots are chained through
+    /// their `next` index; the last one in the chain points at itself.
+    free: Option<usize>,
+    /// Generation assigned to the next inserted entry.
+    next_generation: u64,
+    /// Sum of the weights of all entries in the cache.
+    total_weight: usize,
+    /// Maximum total weight the cache may hold before evicting.
+    max_weight: usize,
+    /// Counters for hits, misses, insertions, and evictions.
+    stats: CacheStats,
 }
 
 #[derive(Debug, Clone)]
-pub struct Entry<T> {
-    /// The value stored at this entry
-    val: T,
+struct Entry<T> {
+    /// The value stored at this entry, or `None` if the slot is vacant
+    val: Option<T>,
     /// Index of the previous entry in the "linked list"
     prev: usize,
     /// Index of the next entry in the "linked list"
     next: usize,
+    /// Generation of the value stored at this entry
+    generation: u64,
+    /// Weight this entry counts towards the cache's weight budget
+    weight: usize,
+    /// Whether this entry is exempt from eviction
+    pinned: bool,
+}
+
+/// A stable reference to an entry in the cache, returned by `insert`.
+/// A handle stops resolving once its entry is evicted or removed, even
+/// if the slot is later reused by another entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryHandle {
+    /// Index of the entry in the backing array
+    index: usize,
+    /// Generation of the entry at the time it was inserted
+    generation: u64,
 }
 
-impl<A: Array> Default for LRUCache<A> {
+/// A snapshot of the cache's activity counters, as returned by `stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups that found a matching entry
+    pub hits: u64,
+    /// Number of lookups that found no matching entry
+    pub misses: u64,
+    /// Number of entries inserted into the cache
+    pub insertions: u64,
+    /// Number of entries removed to make room for new ones
+    pub evictions: u64,
+}
+
+impl<T, const N: usize> Default for LRUCache<T, N> {
     fn default() -> Self {
-        let cache = LRUCache {
-            entries: ArrayVec::new(),
+        assert!(N > 0, "LRUCache needs room for at least one entry");
+
+        let mut cache = LRUCache {
+            entries: core::array::from_fn(|_| Entry {
+                val: None,
+                prev: 0,
+                next: 0,
+                generation: 0,
+                weight: 0,
+                pinned: false,
+            }),
             head: 0,
             tail: 0,
             length: 0,
+            free: None,
+            next_generation: 0,
+            total_weight: 0,
+            max_weight: usize::MAX,
+            stats: CacheStats::default(),
         };
 
-        assert!(
-            cache.entries.capacity() < usize::max_value(),
-            "Capacity overflow"
-        );
-
+        cache.vacate_all();
         cache
     }
 }
 
-impl<T, A> Clone for LRUCache<A>
-where
-    A: Array<Item = Entry<T>>,
-    T: Clone,
-{
+impl<T: Clone, const N: usize> Clone for LRUCache<T, N> {
     fn clone(&self) -> Self {
         Self {
             entries: self.entries.clone(),
             head: self.head,
             tail: self.tail,
             length: self.length,
+            free: self.free,
+            next_generation: self.next_generation,
+            total_weight: self.total_weight,
+            max_weight: self.max_weight,
+            stats: self.stats,
         }
     }
 }
 
-impl<T, A> fmt::Debug for LRUCache<A>
-where
-    A: Array<Item = Entry<T>>,
-    T: fmt::Debug,
-{
+impl<T: fmt::Debug, const N: usize> fmt::Debug for LRUCache<T, N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("LRUCache")
             .field("head", &self.head)
@@ -78,10 +136,22 @@ where
     }
 }
 
-impl<T, A> LRUCache<A>
-where
-    A: Array<Item = Entry<T>>,
-{
+impl<T, const N: usize> LRUCache<T, N> {
+    /// Creates an empty cache that evicts least-recently-used entries
+    /// whenever the total weight of its entries exceeds `max_weight`,
+    /// in addition to evicting when the backing array is full.
+    pub fn with_max_weight(max_weight: usize) -> Self {
+        LRUCache {
+            max_weight,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the maximum number of elements the cache can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
     /// Returns the number of elements in the cache
     pub fn len(&self) -> usize {
         self.length
@@ -91,15 +161,44 @@ where
         self.length == 0
     }
 
+    /// Returns the sum of the weights of all entries in the cache
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    /// Returns the weight budget of the cache
+    pub fn max_weight(&self) -> usize {
+        self.max_weight
+    }
+
+    /// Returns a snapshot of the cache's hit, miss, insertion, and
+    /// eviction counters.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets all of the cache's counters to zero.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
     /// Returns the most-recently-used entry (the one at the head index)
     pub fn front(&self) -> Option<&T> {
-        self.entries.get(self.head as usize).map(|e| &e.val)
+        if self.is_empty() {
+            return None;
+        }
+
+        self.entries.get(self.head).and_then(|e| e.val.as_ref())
     }
 
     /// Returns a mutable reference to the most-recently-used
     /// entry (the one at the head index)
     pub fn front_mut(&mut self) -> Option<&mut T> {
-        self.entries.get_mut(self.head as usize).map(|e| &mut e.val)
+        if self.is_empty() {
+            return None;
+        }
+
+        self.entries.get_mut(self.head).and_then(|e| e.val.as_mut())
     }
 
     /// Performs a lookup on the cache with the given predicate.
@@ -111,10 +210,12 @@ where
         for (i, entry) in self.iter_mut() {
             if let Some(r) = pred(entry) {
                 self.touch_index(i);
+                self.stats.hits += 1;
                 return Some(r);
             }
         }
 
+        self.stats.misses += 1;
         None
     }
 
@@ -124,12 +225,16 @@ where
     where
         F: FnMut(&T) -> bool,
     {
-        match self.iter_mut().find(|&(_, ref x)| pred(x)) {
+        match self.iter_mut().find(|(_, x)| pred(x)) {
             Some((i, _)) => {
                 self.touch_index(i);
+                self.stats.hits += 1;
                 true
             },
-            None => false,
+            None => {
+                self.stats.misses += 1;
+                false
+            },
         }
     }
 
@@ -159,44 +264,231 @@ where
         }
     }
 
+    /// Returns the first item in the cache that matches the given
+    /// predicate, or inserts the value computed by `f` if there is none.
+    /// Either way, the returned entry ends up at the head of the cache.
+    pub fn get_or_insert_with<P, F>(&mut self, pred: P, f: F) -> &mut T
+    where
+        P: FnMut(&T) -> bool,
+        F: FnOnce() -> T,
+    {
+        if !self.touch(pred) {
+            self.insert(f());
+        }
+
+        self.front_mut().expect("cache holds the touched or inserted entry")
+    }
+
+    /// Pins the first item in the cache that matches the given predicate,
+    /// so that it is never selected for eviction. Pinning doesn't count
+    /// as a use of the entry. Returns `true` on a hit, `false` if no
+    /// matches.
+    pub fn pin<F>(&mut self, pred: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.set_pinned(pred, true)
+    }
+
+    /// Unpins the first item in the cache that matches the given
+    /// predicate, making it eligible for eviction again. Returns `true`
+    /// on a hit, `false` if no matches.
+    pub fn unpin<F>(&mut self, pred: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.set_pinned(pred, false)
+    }
+
+    /// Sets the pinned flag of the first item that matches the predicate.
+    fn set_pinned<F>(&mut self, mut pred: F, pinned: bool) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        match self.iter_mut().find(|(_, x)| pred(x)) {
+            Some((i, _)) => {
+                self.entries[i].pinned = pinned;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Touches the entry referred to by the given handle, putting it
+    /// first in the list. Returns `false` if the handle is stale.
+    pub fn touch_handle(&mut self, handle: EntryHandle) -> bool {
+        match self.handle_index(handle) {
+            Some(index) => {
+                self.touch_index(index);
+                self.stats.hits += 1;
+                true
+            },
+            None => {
+                self.stats.misses += 1;
+                false
+            },
+        }
+    }
+
+    /// Returns the entry referred to by the given handle, moving it to
+    /// the head of the cache. Returns `None` if the handle is stale.
+    pub fn get_handle(&mut self, handle: EntryHandle) -> Option<&mut T> {
+        if self.touch_handle(handle) {
+            self.front_mut()
+        } else {
+            None
+        }
+    }
+
+    /// Removes the entry referred to by the given handle from the cache
+    /// and returns its value. Returns `None` if the handle is stale.
+    pub fn remove_handle(&mut self, handle: EntryHandle) -> Option<T> {
+        let index = self.handle_index(handle)?;
+        self.remove_index(index)
+    }
+
+    /// Unlinks the entry at the given index, vacates its slot, and
+    /// returns its value.
+    fn remove_index(&mut self, index: usize) -> Option<T> {
+        self.remove(index);
+        self.total_weight -= self.entries[index].weight;
+        let val = self.entries[index].val.take();
+        self.push_free(index);
+
+        val
+    }
+
+    /// Returns the index of the entry referred to by the given handle,
+    /// if that entry is still in the cache.
+    #[inline]
+    fn handle_index(&self, handle: EntryHandle) -> Option<usize> {
+        match self.entries.get(handle.index) {
+            Some(entry) if entry.generation == handle.generation && entry.val.is_some() => {
+                Some(handle.index)
+            },
+            _ => None,
+        }
+    }
+
     /// Insert a given value in the cache.
     /// The entry becomes the most-recently-used entry in the cache. If the
     /// cache is full, the least-recently-used element is removed.
-    pub fn insert(&mut self, val: T) {
+    /// Returns a handle that can be used to access the entry directly.
+    /// The entry has a weight of 1.
+    pub fn insert(&mut self, val: T) -> EntryHandle {
+        self.insert_weighted(val, 1)
+    }
+
+    /// Insert a given value with the given weight in the cache.
+    /// The entry becomes the most-recently-used entry in the cache.
+    /// Least-recently-used unpinned entries are removed until the total
+    /// weight fits within the budget, or until no other unpinned entries
+    /// are left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache is full and every entry in it is pinned.
+    pub fn insert_weighted(&mut self, val: T, weight: usize) -> EntryHandle {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+
         let entry = Entry {
-            val,
+            val: Some(val),
             prev: 0,
             next: 0,
+            generation,
+            weight,
+            pinned: false,
         };
         
-        // cache is at full capacity 
-        let new_head = if self.length == self.entries.capacity() {
-            // get the index of the oldest entry 
-            let last_index = self.pop_back();
-            // overwrite the oldest entry with the new entry 
-            self.entries[last_index] = entry;
-            // return the index of the newly-overwritten entry
-            last_index
+        let new_head = if let Some(index) = self.pop_free() {
+            self.entries[index] = entry;
+            self.length += 1;
+            index
         } else {
-            self.entries.push(entry);
+            // cache is at full capacity
+            // get the index of the oldest unpinned entry
+            let victim = self
+                .oldest_unpinned(self.length)
+                .expect("cannot insert into a cache full of pinned entries");
+            self.remove(victim);
+            self.total_weight -= self.entries[victim].weight;
+            self.stats.evictions += 1;
+            // overwrite the oldest entry with the new entry 
+            self.entries[victim] = entry;
             self.length += 1;
-            self.entries.len() - 1
+            // return the index of the newly-overwritten entry
+            victim
         };
 
+        self.total_weight += weight;
+        self.stats.insertions += 1;
         self.push_front(new_head);
+
+        // the new entry is at the head, so it is never a candidate here
+        while self.total_weight > self.max_weight {
+            match self.oldest_unpinned(self.length - 1) {
+                Some(victim) => {
+                    self.remove_index(victim);
+                    self.stats.evictions += 1;
+                },
+                None => break,
+            }
+        }
+
+        EntryHandle {
+            index: new_head,
+            generation,
+        }
+    }
+
+    /// Removes every entry for which the given predicate returns `false`.
+    /// The remaining entries keep their relative order, and the vacated
+    /// slots are reused by later insertions.
+    pub fn retain<F>(&mut self, mut pred: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut index = self.head;
+
+        for _ in 0..self.length {
+            let next = self.entries[index].next;
+            let keep = match self.entries[index].val {
+                Some(ref val) => pred(val),
+                None => true,
+            };
+
+            if !keep {
+                self.remove_index(index);
+            }
+
+            index = next;
+        }
     }
 
     /// Clear all entries from the cache.
     pub fn clear(&mut self) {
-        self.entries.clear();
+        self.vacate_all();
         self.head = 0;
         self.tail = 0;
         self.length = 0;
+        self.total_weight = 0;
+    }
+
+    /// Drops every value in the array and chains all slots, in index
+    /// order, into the list of vacant slots.
+    fn vacate_all(&mut self) {
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            entry.val = None;
+            entry.next = if i + 1 < N { i + 1 } else { i };
+        }
+
+        self.free = Some(0);
     }
 
     /// Sets the entry at the given index as the head of the list.
     fn push_front(&mut self, index: usize) {
-        if self.entries.len() == 1 {
+        if self.length == 1 {
             self.tail = index;
         } else {
             self.entries[index].next = self.head;
@@ -206,19 +498,50 @@ where
         self.head = index;
     }
 
-    /// Remove the last entry from the linked list. Returns the index of
-    /// the removed entry.
-    /// Note that this only unlinks the entry from the list, it doesn't
-    /// remove it from the array.
-    fn pop_back(&mut self) -> usize {
-        let old_tail = self.tail;
-        let new_tail = self.entries[old_tail].prev;
-        self.tail = new_tail;
-        old_tail
+    /// Returns the index of the least-recently-used unpinned entry among
+    /// the last `count` entries of the list, if there is one.
+    fn oldest_unpinned(&self, count: usize) -> Option<usize> {
+        let mut index = self.tail;
+
+        for _ in 0..count {
+            if !self.entries[index].pinned {
+                return Some(index);
+            }
+            index = self.entries[index].prev;
+        }
+
+        None
+    }
+
+    /// Adds the slot at the given index to the chain of vacant slots.
+    fn push_free(&mut self, index: usize) {
+        self.entries[index].next = self.free.unwrap_or(index);
+        self.free = Some(index);
+    }
+
+    /// Takes the first slot off the chain of vacant slots.
+    fn pop_free(&mut self) -> Option<usize> {
+        let index = self.free?;
+        let next = self.entries[index].next;
+        self.free = if next == index { None } else { Some(next) };
+
+        Some(index)
+    }
+
+    /// Iterate over the contents of the cache, from most-recently-used
+    /// to least-recently-used. Use `rev` to walk from the
+    /// least-recently-used entry instead.
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            front: self.head,
+            back: self.tail,
+            remaining: self.length,
+            cache: self,
+        }
     }
 
     /// Iterate mutably over the contents of the cache.
-    fn iter_mut(&mut self) -> IterMut<A> {
+    fn iter_mut(&mut self) -> IterMut<'_, T, N> {
         IterMut {
             pos: self.head,
             done: self.is_empty(),
@@ -251,19 +574,72 @@ where
     }
 }
 
+/// Iterator over values in the LRUCache, from most-recently-used
+/// to least-recently-used.
+pub struct Iter<'a, T, const N: usize> {
+    cache: &'a LRUCache<T, N>,
+    /// Index of the next entry to yield from the front
+    front: usize,
+    /// Index of the next entry to yield from the back
+    back: usize,
+    /// Number of entries not yet yielded from either end
+    remaining: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let entry = &self.cache.entries[self.front];
+        self.front = entry.next;
+        self.remaining -= 1;
+
+        entry.val.as_ref()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let entry = &self.cache.entries[self.back];
+        self.back = entry.prev;
+        self.remaining -= 1;
+
+        entry.val.as_ref()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a LRUCache<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// Mutable iterator over values in the LRUCache, from most-recently-used
 /// to least-recently-used.
-struct IterMut<'a, A: 'a + Array> {
-    cache: &'a mut LRUCache<A>,
+struct IterMut<'a, T, const N: usize> {
+    cache: &'a mut LRUCache<T, N>,
     pos: usize,
     done: bool,
 }
 
-impl<'a, T, A> Iterator for IterMut<'a, A>
-where
-    T: 'a,
-    A: 'a + Array<Item = Entry<T>>,
-{
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
     type Item = (usize, &'a mut T);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -283,7 +659,9 @@ where
 
         self.pos = entry.next;
 
-        Some((index, &mut entry.val))
+        let val = entry.val.as_mut().expect("linked entries are never vacant");
+
+        Some((index, val))
     }
 }
 
@@ -291,14 +669,10 @@ where
 mod test {
     use super::*;
 
-    type TestCache = LRUCache<[Entry<i32>; 4]>;
+    type TestCache = LRUCache<i32, 4>;
 
     /// Convenience function for test assertions
-    fn items<T, A>(cache: &mut LRUCache<A>) -> Vec<T>
-    where
-        T: Clone,
-        A: Array<Item = Entry<T>>,
-    {
+    fn items<T: Clone, const N: usize>(cache: &mut LRUCache<T, N>) -> Vec<T> {
         cache.iter_mut().map(|(_, x)| x.clone()).collect()
     }
 
@@ -392,6 +766,226 @@ mod test {
         assert_eq!(items(&mut cache), [], "All items evicted again");
     }
 
+    #[test]
+    fn test_insert_weighted() {
+        let mut cache = TestCache::with_max_weight(10);
+
+        cache.insert_weighted(1, 3);
+        cache.insert_weighted(2, 3);
+        cache.insert_weighted(3, 3);
+        assert_eq!(cache.total_weight(), 9);
+        assert_eq!(items(&mut cache), [3, 2, 1]);
+
+        cache.insert_weighted(4, 5);
+        assert_eq!(cache.total_weight(), 8);
+        assert_eq!(
+            items(&mut cache),
+            [4, 3],
+            "Least-recently-used items evicted until within budget"
+        );
+
+        cache.insert(5);
+        cache.insert(6);
+        assert_eq!(cache.total_weight(), 10);
+        assert_eq!(items(&mut cache), [6, 5, 4, 3]);
+
+        cache.insert(7);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.total_weight(), 8, "Evicted item's weight released");
+
+        cache.insert_weighted(8, 20);
+        assert_eq!(items(&mut cache), [8], "Oversized item kept on its own");
+        assert_eq!(cache.total_weight(), 20);
+
+        cache.clear();
+        assert_eq!(cache.total_weight(), 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut cache = TestCache::default();
+        assert_eq!(cache.stats(), CacheStats::default());
+
+        for i in 1..=5 {
+            cache.insert(i);
+        }
+
+        cache.touch(|x| *x == 3);
+        cache.fetch(|x| *x == 1);
+        cache.lookup(|x| if *x == 4 { Some(()) } else { None });
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                insertions: 5,
+                evictions: 1,
+            }
+        );
+
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+        assert_eq!(cache.len(), 4, "Resetting stats leaves entries alone");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut cache = TestCache::default();
+        let handle = cache.insert(1);
+        cache.insert(2);
+        cache.insert(3);
+        cache.insert(4);
+
+        cache.retain(|x| x % 2 == 0);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(items(&mut cache), [4, 2], "Order of kept items preserved");
+        assert_eq!(cache.get_handle(handle), None, "Removed handle is stale.");
+
+        cache.insert(5);
+        cache.insert(6);
+        assert_eq!(items(&mut cache), [6, 5, 4, 2], "Vacated slots reused");
+
+        cache.retain(|_| false);
+        assert!(cache.is_empty());
+        assert_eq!(items(&mut cache), []);
+        assert_eq!(cache.front(), None);
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut cache = TestCache::default();
+        cache.insert(1);
+        cache.insert(2);
+        cache.insert(3);
+
+        assert_eq!(*cache.get_or_insert_with(|x| *x == 1, || 10), 1);
+        assert_eq!(items(&mut cache), [1, 3, 2], "Matching item moved to front.");
+
+        *cache.get_or_insert_with(|x| *x == 4, || 4) += 10;
+        assert_eq!(items(&mut cache), [14, 1, 3, 2], "Missing item inserted.");
+
+        cache.get_or_insert_with(|x| *x == 5, || 5);
+        assert_eq!(
+            items(&mut cache),
+            [5, 14, 1, 3],
+            "Least-recently-used item evicted"
+        );
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut cache = TestCache::default();
+        assert_eq!(cache.iter().next(), None);
+        assert_eq!(cache.iter().next_back(), None);
+
+        for i in 1..=5 {
+            cache.insert(i);
+        }
+        cache.touch(|x| *x == 3);
+
+        assert_eq!(cache.iter().copied().collect::<Vec<_>>(), [3, 5, 4, 2]);
+        assert_eq!(
+            cache.iter().rev().copied().collect::<Vec<_>>(),
+            [2, 4, 5, 3],
+            "Ordered from least- to most-recent"
+        );
+
+        let mut iter = cache.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&5));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_pin() {
+        let mut cache = TestCache::default();
+        for i in 1..=4 {
+            cache.insert(i);
+        }
+
+        assert!(cache.pin(|x| *x == 1));
+        assert!(cache.pin(|x| *x == 2));
+        assert!(!cache.pin(|x| *x == 9));
+        assert_eq!(items(&mut cache), [4, 3, 2, 1], "Pinning doesn't touch.");
+
+        cache.insert(5);
+        assert_eq!(
+            items(&mut cache),
+            [5, 4, 2, 1],
+            "Oldest unpinned item evicted"
+        );
+
+        assert!(cache.unpin(|x| *x == 1));
+        cache.insert(6);
+        assert_eq!(items(&mut cache), [6, 5, 4, 2], "Unpinned item evicted");
+    }
+
+    #[test]
+    fn test_pin_weighted() {
+        let mut cache = TestCache::with_max_weight(4);
+        cache.insert_weighted(1, 2);
+        cache.insert_weighted(2, 2);
+        cache.pin(|x| *x == 1);
+
+        cache.insert_weighted(3, 2);
+        assert_eq!(items(&mut cache), [3, 1], "Pinned item kept over budget");
+
+        cache.pin(|x| *x == 3);
+        cache.insert_weighted(4, 2);
+        assert_eq!(items(&mut cache), [4, 3, 1]);
+        assert_eq!(cache.total_weight(), 6, "Budget exceeded by pinned items");
+    }
+
+    #[test]
+    #[should_panic(expected = "pinned")]
+    fn test_insert_all_pinned() {
+        let mut cache = LRUCache::<i32, 2>::default();
+        cache.insert(1);
+        cache.insert(2);
+        cache.pin(|_| true);
+        cache.pin(|x| *x == 1);
+
+        cache.insert(3);
+    }
+
+    #[test]
+    fn test_handles() {
+        let mut cache = TestCache::default();
+        let one = cache.insert(1);
+        let two = cache.insert(2);
+        let three = cache.insert(3);
+
+        assert!(cache.touch_handle(one));
+        assert_eq!(items(&mut cache), [1, 3, 2], "Touched item moved to front.");
+
+        *cache.get_handle(two).unwrap() = 20;
+        assert_eq!(items(&mut cache), [20, 1, 3]);
+
+        assert_eq!(cache.remove_handle(three), Some(3));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(items(&mut cache), [20, 1]);
+        assert_eq!(cache.remove_handle(three), None, "Handle is stale.");
+
+        // the vacated slot is reused without invalidating other handles
+        let four = cache.insert(4);
+        assert_eq!(items(&mut cache), [4, 20, 1]);
+        assert_eq!(cache.get_handle(three), None);
+        assert_eq!(cache.get_handle(four), Some(&mut 4));
+
+        cache.insert(5);
+        cache.insert(6);
+        assert_eq!(items(&mut cache), [6, 5, 4, 20], "Least-recently-used item evicted");
+        assert!(!cache.touch_handle(one), "Evicted handle is stale.");
+
+        cache.clear();
+        assert_eq!(cache.get_handle(four), None, "Cleared handle is stale.");
+    }
+
     #[quickcheck]
     fn touch(num: i32) {
         let first = num;
@@ -0,0 +1,91 @@
+use crate::{CacheStats, LRUCache};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+/// Serializes the cache as a sequence of its values, from most- to
+/// least-recently-used. Entry weights and the weight budget are not
+/// part of the snapshot.
+impl<T: Serialize, const N: usize> Serialize for LRUCache<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+
+        for val in self.iter() {
+            seq.serialize_element(val)?;
+        }
+
+        seq.end()
+    }
+}
+
+/// Rebuilds a cache from a sequence of values ordered from most- to
+/// least-recently-used, preserving that order. If the sequence holds
+/// more than `N` values, only the `N` most-recently-used are kept.
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for LRUCache<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+        let mut cache = Self::default();
+
+        for val in values.into_iter().rev() {
+            cache.insert(val);
+        }
+
+        cache.stats = CacheStats::default();
+
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::LRUCache;
+    use serde::de::value::{Error, SeqDeserializer};
+    use serde::Deserialize;
+    use serde_test::{assert_ser_tokens, Token};
+
+    /// Deserializes a cache from values listed most-recently-used first
+    fn restore<const N: usize>(values: Vec<i32>) -> LRUCache<i32, N> {
+        let deserializer = SeqDeserializer::<_, Error>::new(values.into_iter());
+        LRUCache::deserialize(deserializer).unwrap()
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut cache = LRUCache::<i32, 4>::default();
+        cache.insert(1);
+        cache.insert(2);
+        cache.insert(3);
+        cache.touch(|x| *x == 1);
+
+        assert_ser_tokens(
+            &cache,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::I32(1),
+                Token::I32(3),
+                Token::I32(2),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let mut cache = restore::<4>(vec![1, 3, 2]);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.front(), Some(&1), "Recency order restored");
+        assert_eq!(cache.stats().insertions, 0, "Restoring starts fresh stats");
+
+        cache.insert(4);
+        cache.insert(5);
+        assert!(!cache.touch(|x| *x == 2), "Least-recently-used item evicted");
+        assert!(cache.touch(|x| *x == 3));
+    }
+
+    #[test]
+    fn test_deserialize_overfull() {
+        let mut cache = restore::<2>(vec![1, 2, 3]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.front(), Some(&1));
+        assert!(!cache.touch(|x| *x == 3), "Only the most recent values kept");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{Iter, LRUCache};
+use core::fmt;
+use core::iter::Chain;
+
+/// Share of the capacity given to the protected segment by `default`.
+const DEFAULT_PROTECTED_RATIO: f64 = 0.8;
+
+/// A segmented LRU cache holding up to `N` values of type `T`.
+/// New entries land in a probationary segment and are only promoted to
+/// the protected segment when they are accessed again, so a one-off scan
+/// over many entries can't flush out the entries that are used
+/// repeatedly. When the protected segment overflows, its
+/// least-recently-used entry is demoted back to the head of the
+/// probationary segment; entries are only evicted from the probationary
+/// segment.
+pub struct SlruCache<T, const N: usize> {
+    /// Entries that have been accessed at most once since insertion.
+    probationary: LRUCache<T, N>,
+    /// Entries that have been accessed at least twice.
+    protected: LRUCache<T, N>,
+    /// Maximum number of entries in the protected segment. The
+    /// probationary segment holds up to `N - protected_capacity`.
+    protected_capacity: usize,
+}
+
+impl<T, const N: usize> Default for SlruCache<T, N> {
+    /// Creates a cache that gives 80% of its capacity to the protected
+    /// segment.
+    fn default() -> Self {
+        Self::with_protected_ratio(DEFAULT_PROTECTED_RATIO)
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SlruCache<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            probationary: self.probationary.clone(),
+            protected: self.protected.clone(),
+            protected_capacity: self.protected_capacity,
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SlruCache<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlruCache")
+            .field("probationary", &self.probationary)
+            .field("protected", &self.protected)
+            .finish()
+    }
+}
+
+impl<T, const N: usize> SlruCache<T, N> {
+    /// Creates a cache that gives the given share of its capacity, between
+    /// 0.0 inclusive and 1.0 exclusive, to the protected segment. The
+    /// probationary segment always keeps room for at least one entry.
+    pub fn with_protected_ratio(ratio: f64) -> Self {
+        assert!((0.0..1.0).contains(&ratio));
+
+        let protected_capacity = ((N as f64) * ratio) as usize;
+
+        SlruCache {
+            probationary: LRUCache::default(),
+            protected: LRUCache::default(),
+            protected_capacity: protected_capacity.min(N - 1),
+        }
+    }
+
+    /// Returns the maximum number of elements the cache can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the maximum number of elements in the protected segment
+    pub fn protected_capacity(&self) -> usize {
+        self.protected_capacity
+    }
+
+    /// Returns the number of elements in the cache
+    pub fn len(&self) -> usize {
+        self.probationary.len() + self.protected.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probationary.is_empty() && self.protected.is_empty()
+    }
+
+    /// Touches the first item in the cache that matches the given
+    /// predicate, promoting it to the protected segment if it was still
+    /// probationary. Returns `true` on a hit, `false` if no matches.
+    pub fn touch<F>(&mut self, pred: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.fetch(pred).is_some()
+    }
+
+    /// Returns the first item in the cache that matches the given
+    /// predicate, promoting it to the protected segment if it was still
+    /// probationary.
+    pub fn fetch<F>(&mut self, mut pred: F) -> Option<&mut T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        if self.protected.touch(&mut pred) {
+            return self.protected.front_mut();
+        }
+
+        let (index, _) = self.probationary.iter_mut().find(|(_, x)| pred(x))?;
+        let val = self.probationary.remove_index(index)?;
+
+        Some(self.promote(val))
+    }
+
+    /// Insert a given value in the probationary segment of the cache.
+    /// If that segment is full, its least-recently-used element is
+    /// removed.
+    pub fn insert(&mut self, val: T) {
+        self.probationary.insert(val);
+        self.trim_probationary();
+    }
+
+    /// Clear all entries from the cache.
+    pub fn clear(&mut self) {
+        self.probationary.clear();
+        self.protected.clear();
+    }
+
+    /// Iterate over the contents of the cache: the protected segment
+    /// from most- to least-recently-used, followed by the probationary
+    /// segment in the same order.
+    pub fn iter(&self) -> Chain<Iter<'_, T, N>, Iter<'_, T, N>> {
+        self.protected.iter().chain(self.probationary.iter())
+    }
+
+    /// Puts a value at the head of the protected segment, demoting
+    /// entries to the probationary segment until the protected segment
+    /// fits, and returns a reference to the promoted value.
+    fn promote(&mut self, val: T) -> &mut T {
+        self.protected.insert(val);
+
+        while self.protected.len() > self.protected_capacity {
+            let demoted = self
+                .protected
+                .remove_index(self.protected.tail)
+                .expect("protected segment is not empty");
+            self.probationary.insert(demoted);
+            self.trim_probationary();
+        }
+
+        // with no room in the protected segment, the promoted value is
+        // demoted straight back to the head of the probationary segment
+        if self.protected.is_empty() {
+            self.probationary.front_mut()
+        } else {
+            self.protected.front_mut()
+        }
+        .expect("promoted value is at the head of a segment")
+    }
+
+    /// Evicts least-recently-used probationary entries until that segment
+    /// fits in the capacity left over by the protected segment.
+    fn trim_probationary(&mut self) {
+        while self.probationary.len() > N - self.protected_capacity {
+            self.probationary.remove_index(self.probationary.tail);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type TestCache = SlruCache<i32, 4>;
+
+    /// Convenience function for test assertions
+    fn items(cache: &TestCache) -> Vec<i32> {
+        cache.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(TestCache::default().protected_capacity(), 3);
+        assert_eq!(TestCache::with_protected_ratio(0.5).protected_capacity(), 2);
+        assert_eq!(TestCache::with_protected_ratio(0.0).protected_capacity(), 0);
+        assert_eq!(TestCache::with_protected_ratio(0.99).protected_capacity(), 3);
+    }
+
+    #[test]
+    fn test_promotion() {
+        let mut cache = TestCache::with_protected_ratio(0.5);
+        cache.insert(1);
+        cache.insert(2);
+        cache.insert(3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(items(&cache), [3, 2], "Probationary segment evicts on its own");
+
+        assert!(cache.touch(|x| *x == 2));
+        assert!(!cache.touch(|x| *x == 1));
+        assert_eq!(items(&cache), [2, 3], "Touched item promoted");
+
+        // a scan of new entries only churns the probationary segment
+        for i in 10..20 {
+            cache.insert(i);
+        }
+        assert_eq!(items(&cache), [2, 19, 18]);
+
+        *cache.fetch(|x| *x == 18).unwrap() += 1;
+        cache.touch(|x| *x == 2);
+        assert_eq!(items(&cache), [2, 19, 19]);
+    }
+
+    #[test]
+    fn test_demotion() {
+        let mut cache = TestCache::with_protected_ratio(0.5);
+        for i in 1..=2 {
+            cache.insert(i);
+            cache.touch(|x| *x == i);
+        }
+        cache.insert(3);
+        assert_eq!(items(&cache), [2, 1, 3]);
+
+        cache.touch(|x| *x == 3);
+        assert_eq!(
+            items(&cache),
+            [3, 2, 1],
+            "Least-recently-used protected item demoted to probation"
+        );
+
+        cache.insert(4);
+        cache.insert(5);
+        assert_eq!(items(&cache), [3, 2, 5, 4], "Demoted item evicted first");
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_no_protected_segment() {
+        let mut cache = TestCache::with_protected_ratio(0.0);
+        cache.insert(1);
+        cache.insert(2);
+
+        assert_eq!(cache.fetch(|x| *x == 1), Some(&mut 1));
+        assert_eq!(items(&cache), [1, 2], "Plain LRU behaviour");
+    }
+}
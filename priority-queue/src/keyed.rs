@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+/// A max-priority queue of unique keys, each with a priority that can
+/// be changed or removed in O(log n) time after it was pushed. A hash
+/// index maps every key to its position in the binary heap, and is kept
+/// up to date whenever the heap swaps two entries.
+pub struct KeyedPriorityQueue<K, P> {
+    /// The key-priority pairs, in heap order by priority
+    heap: Vec<(K, P)>,
+    /// The position of every key in `heap`
+    index: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for KeyedPriorityQueue<K, P> {
+    fn default() -> Self {
+        KeyedPriorityQueue {
+            heap: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> KeyedPriorityQueue<K, P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns the key with the highest priority, along with its priority
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.heap.first().map(|(key, priority)| (key, priority))
+    }
+
+    /// Returns the priority of the given key, if it is in the queue
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.index.get(key).map(|&pos| &self.heap[pos].1)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Inserts the key with the given priority. If the key was already
+    /// in the queue, its priority is changed instead and the old one is
+    /// returned.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if self.contains_key(&key) {
+            return self.change_priority(&key, priority);
+        }
+
+        let pos = self.heap.len();
+        self.index.insert(key.clone(), pos);
+        self.heap.push((key, priority));
+        self.bubble_up(pos);
+
+        None
+    }
+
+    /// Removes and returns the key with the highest priority, along with
+    /// its priority
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        Some(self.remove_at(0))
+    }
+
+    /// Changes the priority of the given key and returns the old one, or
+    /// returns `None` and changes nothing if the key isn't in the queue
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        let pos = *self.index.get(key)?;
+        let old = mem::replace(&mut self.heap[pos].1, priority);
+        self.restore(pos);
+
+        Some(old)
+    }
+
+    /// Removes the given key from the queue and returns its priority
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let pos = *self.index.get(key)?;
+
+        Some(self.remove_at(pos).1)
+    }
+
+    /// Removes the entry at the given heap position, moving the last
+    /// entry into its spot
+    fn remove_at(&mut self, pos: usize) -> (K, P) {
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+
+        let (key, priority) = self.heap.pop().expect("heap is not empty");
+        self.index.remove(&key);
+        if pos < self.heap.len() {
+            self.restore(pos);
+        }
+
+        (key, priority)
+    }
+
+    /// Moves the entry at the given position up or down, whichever way
+    /// its priority requires
+    fn restore(&mut self, pos: usize) {
+        if pos > 0 && self.heap[pos].1 > self.heap[(pos - 1) / 2].1 {
+            self.bubble_up(pos);
+        } else {
+            self.sift_down(pos);
+        }
+    }
+
+    fn bubble_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.heap[pos].1 <= self.heap[parent].1 {
+                break;
+            }
+
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let len = self.heap.len();
+
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= len {
+                break;
+            }
+            if child + 1 < len && self.heap[child + 1].1 > self.heap[child].1 {
+                child += 1;
+            }
+            if self.heap[child].1 <= self.heap[pos].1 {
+                break;
+            }
+
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    /// Swaps two heap entries and updates their positions in the index
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        *self.index.get_mut(&self.heap[a].0).expect("every key is indexed") = a;
+        *self.index.get_mut(&self.heap[b].0).expect("every key is indexed") = b;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Checks the heap order and that the index points at every entry
+    fn check<K: Hash + Eq + Clone, P: Ord>(queue: &KeyedPriorityQueue<K, P>) {
+        assert_eq!(queue.index.len(), queue.heap.len());
+        for (pos, (key, priority)) in queue.heap.iter().enumerate() {
+            assert_eq!(queue.index[key], pos, "Index is stale");
+            if pos > 0 {
+                assert!(queue.heap[(pos - 1) / 2].1 >= *priority, "Heap order broken");
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut queue = KeyedPriorityQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+
+        for (key, priority) in [("a", 3), ("b", 7), ("c", 1), ("d", 5), ("e", 9)] {
+            assert_eq!(queue.push(key, priority), None);
+            check(&queue);
+        }
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.peek(), Some((&"e", &9)));
+
+        assert_eq!(queue.push("c", 8), Some(1), "Pushing an existing key updates it");
+        assert_eq!(queue.len(), 5);
+
+        let mut popped = vec![];
+        while let Some((key, _)) = queue.pop() {
+            check(&queue);
+            popped.push(key);
+        }
+        assert_eq!(popped, ["e", "c", "b", "d", "a"]);
+    }
+
+    #[test]
+    fn test_change_priority() {
+        let mut queue = KeyedPriorityQueue::new();
+        for task in 0..10 {
+            queue.push(task, task * 10);
+        }
+
+        assert_eq!(queue.change_priority(&0, 100), Some(0));
+        check(&queue);
+        assert_eq!(queue.peek(), Some((&0, &100)));
+
+        assert_eq!(queue.change_priority(&0, -1), Some(100));
+        check(&queue);
+        assert_eq!(queue.peek(), Some((&9, &90)));
+        assert_eq!(queue.priority(&0), Some(&-1));
+
+        assert_eq!(queue.change_priority(&42, 5), None);
+        assert!(!queue.contains_key(&42));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut queue = KeyedPriorityQueue::new();
+        for task in 0..10 {
+            queue.push(task.to_string(), task);
+        }
+
+        assert_eq!(queue.remove(&"9".to_string()), Some(9), "Removing the top");
+        assert_eq!(queue.remove(&"4".to_string()), Some(4));
+        assert_eq!(queue.remove(&"4".to_string()), None);
+        check(&queue);
+        assert_eq!(queue.len(), 8);
+        assert_eq!(queue.priority(&"4".to_string()), None);
+        assert_eq!(queue.peek(), Some((&"8".to_string(), &8)));
+    }
+}
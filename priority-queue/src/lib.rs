@@ -1,39 +1,103 @@
-#![allow(dead_code)]
-
 use std::vec;
 use std::slice;
+use std::fmt;
 use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+mod keyed;
+mod pairing;
+#[cfg(feature = "rayon")]
+mod parallel;
+
+pub use keyed::KeyedPriorityQueue;
+pub use pairing::PairingHeap;
 
-struct PriorityQueue<T> {
+/// A predicate telling which elements have been deleted, passed to
+/// `mark_deleted`
+type Deleted<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+pub struct PriorityQueue<T, F = fn(&T, &T) -> Ordering> {
     /// The Vec that stores the priority queue elements 
     storage: Vec<T>,
     /// A generic comparator function that returns and Ordering of the 
-    /// elements in the priority queue 
-    comparator: fn(&T, &T) -> Ordering,
+    /// elements in the priority queue. It can be a closure, so the
+    /// ordering may depend on state captured from the environment
+    comparator: F,
+    /// The predicate passed to `mark_deleted`, if any. Elements matching
+    /// it are dropped once they reach the front of the queue
+    deleted: Option<Deleted<T>>,
 }
 
-// T needs to implement the `Ord` trait, so there must exist
-// an ordering over T 
-// `<T: Ord>` is a trait bound 
-impl<T: Ord> PriorityQueue<T> {
+// The comparator decides the order of the elements, so T doesn't
+// need to implement `Ord` unless the default comparator is used
+impl<T, F> PriorityQueue<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
     /// New PriorityQueue instance with specified comparator
-    pub fn new_with(comparator: fn(&T, &T) -> Ordering) -> Self {
+    pub fn new_with(comparator: F) -> Self {
         PriorityQueue {
             storage: Vec::new(),
             comparator,
+            deleted: None,
         }
     }
 
+    /// Returns the number of elements the priority queue can hold
+    /// without reallocating
+    pub fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    /// Reserves room for at least `additional` more elements
+    pub fn reserve(&mut self, additional: usize) {
+        self.storage.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the priority queue as much as possible
+    pub fn shrink_to_fit(&mut self) {
+        self.storage.shrink_to_fit();
+    }
+
     /// Returns a reference to the priority value, which
     /// is always the element at index 0 in the storage vec
+    /// unless that one has been marked as deleted
     pub fn peek(&self) -> Option<&T> {
-        self.storage.first()
+        match self.storage.first() {
+            Some(top) if self.is_deleted(top) => self.iter_sorted().next(),
+            top => top,
+        }
+    }
+
+    /// Returns a guard giving mutable access to the priority value.
+    /// When the guard is dropped, the value is sifted down to its proper
+    /// spot, so its priority can be lowered in place instead of popping
+    /// and re-inserting it
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, F>> {
+        self.skip_deleted();
+
+        if self.storage.is_empty() {
+            None
+        } else {
+            Some(PeekMut { queue: self, sift: false })
+        }
     }
 
+    /// Returns the number of elements in storage, including ones that
+    /// have been marked as deleted but haven't been dropped yet. Telling
+    /// them apart would mean checking every element, so call
+    /// `purge_deleted` first for an exact count
     pub fn len(&self) -> usize {
         self.storage.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
     /// Takes ownership of value and inserts it
     pub fn insert(&mut self, value: T) {
         let old_len = self.storage.len();
@@ -46,8 +110,84 @@ impl<T: Ord> PriorityQueue<T> {
         self.bubble_up(0, old_len);
     }
 
-    /// Removes and returns the owned priority value
+    /// Moves all the elements of `other` into this priority queue,
+    /// leaving `other` empty. The merged queue keeps this queue's
+    /// comparator. Small batches are inserted one by one; otherwise the
+    /// whole heap is rebuilt in O(n + m) time.
+    pub fn append<G>(&mut self, other: &mut PriorityQueue<T, G>) {
+        // this queue may not consider the elements `other` deleted as
+        // deleted, so they are dropped rather than moved
+        if let Some(deleted) = &other.deleted {
+            other.storage.retain(|x| !deleted(x));
+        }
+
+        let start = self.len();
+        self.storage.append(&mut other.storage);
+        self.rebuild_tail(start);
+    }
+
+    /// Removes and returns the owned priority value, first dropping any
+    /// elements marked as deleted that are ahead of it
     pub fn pop(&mut self) -> Option<T> {
+        self.skip_deleted();
+        self.pop_front()
+    }
+
+    /// Marks every element matching the predicate as deleted, including
+    /// elements inserted later on. Rather than being removed from the
+    /// heap right away, deleted elements are dropped once they reach the
+    /// front of the queue, which is much cheaper than `remove_where` when
+    /// many elements go stale, as in Dijkstra's algorithm. Until then
+    /// they still count towards `len` and show up in `iter`.
+    ///
+    /// A queue has a single predicate, which replaces any earlier one and
+    /// is called once for each element reaching the front. It usually
+    /// checks a set of deleted keys that the caller keeps up to date, so
+    /// deleting more elements only means adding to that set.
+    pub fn mark_deleted<P>(&mut self, pred: P)
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.deleted = Some(Arc::new(pred));
+    }
+
+    /// Removes the predicate passed to `mark_deleted`, so the elements it
+    /// matched that are still in storage are no longer deleted
+    pub fn unmark_deleted(&mut self) {
+        self.deleted = None;
+    }
+
+    /// Removes all the elements marked as deleted from storage right
+    /// away, rebuilding the heap in O(n) time if there were any
+    pub fn purge_deleted(&mut self) {
+        let deleted = match &self.deleted {
+            Some(deleted) => deleted,
+            None => return,
+        };
+
+        let len = self.storage.len();
+        self.storage.retain(|x| !deleted(x));
+
+        if self.len() < len {
+            self.rebuild();
+        }
+    }
+
+    fn is_deleted(&self, value: &T) -> bool {
+        self.deleted.as_ref().is_some_and(|deleted| deleted(value))
+    }
+
+    /// Pops elements off the front of the queue until the priority value
+    /// is one that hasn't been marked as deleted
+    fn skip_deleted(&mut self) {
+        while self.storage.first().is_some_and(|top| self.is_deleted(top)) {
+            self.pop_front();
+        }
+    }
+
+    /// Removes and returns the element at index 0 in the storage vec,
+    /// whether or not it has been marked as deleted
+    fn pop_front(&mut self) -> Option<T> {
         match self.len() {
             0 => None,
             1 => self.storage.pop(),
@@ -63,6 +203,53 @@ impl<T: Ord> PriorityQueue<T> {
         }
     }
 
+    /// Removes and returns the first element, in storage order, that
+    /// matches the given predicate. The last element takes its place and
+    /// is sifted up or down to restore the heap property
+    pub fn remove_where<P>(&mut self, pred: P) -> Option<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let pos = self.storage.iter().position(pred)?;
+        Some(self.remove_at(pos))
+    }
+
+    /// Keeps only the elements that match the given predicate. Sifting
+    /// after each removal would move elements the scan hasn't checked yet
+    /// behind it, so instead the heap is rebuilt once in O(n) time if
+    /// anything was removed
+    pub fn retain<P>(&mut self, mut pred: P)
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let len = self.len();
+        self.storage.retain(|x| pred(x));
+
+        if self.len() < len {
+            self.rebuild();
+        }
+    }
+
+    /// Removes the element at the given storage index, moving the last
+    /// element into its spot and sifting it in whichever direction it
+    /// needs to go
+    fn remove_at(&mut self, pos: usize) -> T {
+        let removed = self.storage.swap_remove(pos);
+
+        if pos < self.len() {
+            if pos > 0
+                && (self.comparator)(&self.storage[pos], &self.storage[(pos - 1) / 2])
+                    == Ordering::Greater
+            {
+                self.bubble_up(0, pos);
+            } else {
+                self.sift_down(pos);
+            }
+        }
+
+        removed
+    }
+
     /// Swaps an element up the priority queue with its parent until
     /// it reaches an appropriate spot in the queue
     fn bubble_up(&mut self, start: usize, mut pos: usize) {
@@ -78,10 +265,53 @@ impl<T: Ord> PriorityQueue<T> {
         }
     }
 
+    /// Restores the heap property after elements were pushed onto the
+    /// storage vec from index `start` onwards, by either bubbling each of
+    /// them up or rebuilding the whole heap, whichever is cheaper
+    fn rebuild_tail(&mut self, start: usize) {
+        if self.prefers_rebuild(start) {
+            self.rebuild();
+        } else {
+            for pos in start..self.len() {
+                self.bubble_up(0, pos);
+            }
+        }
+    }
+
+    /// Whether rebuilding the whole heap is cheaper than bubbling up the
+    /// elements from index `start` onwards one by one
+    fn prefers_rebuild(&self, start: usize) -> bool {
+        let len = self.len();
+        let added = len - start;
+
+        // inserting costs about log2(len) swaps per added element, while
+        // rebuilding costs about 2 per element in the whole heap
+        added > 0 && 2 * len < added * (len.ilog2() as usize)
+    }
+
+    /// Restores the heap property over the whole storage vec by sifting
+    /// down every element that has children, from the last one up
+    fn rebuild(&mut self) {
+        for pos in (0..self.len() / 2).rev() {
+            self.sift_down(pos);
+        }
+    }
+
     /// Swaps an element down the priority queue with its higher-priority
     /// child until it reaches an appropriate spot in the queue
-    fn sift_down(&mut self, mut pos: usize) {
-        let end = self.len() - 1;
+    fn sift_down(&mut self, pos: usize) {
+        let len = self.len();
+        self.sift_down_range(pos, len);
+    }
+
+    /// Like `sift_down`, but only treats the first `len` elements of
+    /// storage as part of the heap
+    fn sift_down_range(&mut self, mut pos: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let end = len - 1;
         let mut child = 2 * pos + 1;
         
         while child <= end {
@@ -105,22 +335,77 @@ impl<T: Ord> PriorityQueue<T> {
     }
 
     /// Initialize an Iter instance to keep track of
-    /// the state of elements in our iterator
-    fn iter(&self) -> Iter<'_, T> {
+    /// the state of elements in our iterator.
+    /// Elements are visited in storage order, which is arbitrary apart
+    /// from the priority value coming first; use `iter_sorted` to visit
+    /// them in priority order
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter { iter: self.storage.iter() } 
     }
 
-    fn into_iter(&mut self) -> IntoIter<T> {
-        let mut iter = vec![];
+    /// Returns an iterator over the elements from highest to lowest
+    /// priority, without modifying the priority queue. Each step costs
+    /// O(log k) time, where k is the number of steps taken so far
+    pub fn iter_sorted(&self) -> IterSorted<'_, T, F> {
+        IterSorted {
+            queue: self,
+            frontier: if self.storage.is_empty() { vec![] } else { vec![0] },
+        }
+    }
+
+    /// Returns the elements in storage order: the priority value comes
+    /// first, and the children of the element at index `i` are at
+    /// `2 * i + 1` and `2 * i + 2`
+    pub fn as_slice(&self) -> &[T] {
+        &self.storage
+    }
+
+    /// Consumes the priority queue and returns its storage vec, without
+    /// the elements marked as deleted. The rest are in the same order as
+    /// `as_slice` if none were deleted, or in some other heap order
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.purge_deleted();
+        self.storage
+    }
+
+    /// Consumes the priority queue and returns its elements sorted from
+    /// lowest to highest priority, by repeatedly moving the priority
+    /// value to the end of the storage vec in place
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.purge_deleted();
+        let mut end = self.len();
 
-        while let Some(val) = self.pop() {
-            iter.push(val);
+        while end > 1 {
+            end -= 1;
+            self.storage.swap(0, end);
+            self.sift_down_range(0, end);
         }
 
-        iter.reverse();
+        self.storage
+    }
 
-        IntoIter {
-            iter: iter.into_iter(),
+    /// Returns an iterator that pops the elements of the priority queue
+    /// from highest to lowest priority. Elements that haven't been
+    /// popped when the iterator is dropped are removed anyway. Elements
+    /// marked as deleted are removed when draining starts, and the
+    /// predicate isn't checked again while draining
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, F> {
+        // purging up front keeps the length exact while draining
+        self.purge_deleted();
+        DrainSorted { queue: self }
+    }
+}
+
+// T needs to implement the `Ord` trait, so there must exist
+// an ordering over T 
+// `<T: Ord>` is a trait bound 
+impl<T: Ord> PriorityQueue<T> {
+    /// New max-heap PriorityQueue instance with room for at least
+    /// `capacity` elements before it needs to reallocate
+    pub fn with_capacity(capacity: usize) -> Self {
+        PriorityQueue {
+            storage: Vec::with_capacity(capacity),
+            ..Self::default()
         }
     }
 }
@@ -131,18 +416,219 @@ impl<T: Ord> Default for PriorityQueue<T> {
     fn default() -> Self {
         PriorityQueue {
             storage: Vec::new(),
-            comparator: |a: &T, b: &T| a.cmp(b)
+            comparator: |a: &T, b: &T| a.cmp(b),
+            deleted: None,
+        }
+    }
+}
+
+impl<T, F> PriorityQueue<T, F> {
+    /// The elements in storage order, skipping the ones marked as deleted
+    fn live(&self) -> impl Iterator<Item = &T> {
+        self.storage.iter().filter(move |x| !self.deleted.as_ref().is_some_and(|deleted| deleted(x)))
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for PriorityQueue<T, F> {
+    /// Shows the elements in storage order, leaving out the ones marked
+    /// as deleted
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.live()).finish()
+    }
+}
+
+/// Two priority queues are equal if they hold the same elements the same
+/// number of times, regardless of their comparators or storage order.
+/// Elements marked as deleted don't count
+impl<T: Ord, F, G> PartialEq<PriorityQueue<T, G>> for PriorityQueue<T, F> {
+    fn eq(&self, other: &PriorityQueue<T, G>) -> bool {
+        let mut ours: Vec<_> = self.live().collect();
+        let mut theirs: Vec<_> = other.live().collect();
+        ours.sort();
+        theirs.sort();
+
+        ours == theirs
+    }
+}
+
+impl<T: Ord, F> Eq for PriorityQueue<T, F> {}
+
+impl<T, F> Extend<T> for PriorityQueue<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.len();
+        self.storage.extend(iter);
+        self.rebuild_tail(start);
+    }
+}
+
+impl<T: Ord> FromIterator<T> for PriorityQueue<T> {
+    /// Collects the elements into a max heap, built in O(n) time
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut pq = PriorityQueue::default();
+        pq.storage.extend(iter);
+        pq.rebuild();
+
+        pq
+    }
+}
+
+/// Mutable access to the priority value of a priority queue, returned
+/// by `peek_mut`
+pub struct PeekMut<'a, T: 'a, F: 'a + Fn(&T, &T) -> Ordering> {
+    queue: &'a mut PriorityQueue<T, F>,
+    /// Whether the value may have been modified, and so needs sifting
+    sift: bool,
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> PeekMut<'a, T, F> {
+    /// Removes the peeked value from the priority queue and returns it
+    pub fn pop(mut this: PeekMut<'a, T, F>) -> T {
+        // popping restores the heap property, so there's nothing left
+        // for the drop to do
+        this.sift = false;
+        this.queue.pop_front().expect("PeekMut is only created for non-empty queues")
+    }
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> Deref for PeekMut<'a, T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.queue.storage[0]
+    }
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> DerefMut for PeekMut<'a, T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.sift = true;
+        &mut self.queue.storage[0]
+    }
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> Drop for PeekMut<'a, T, F> {
+    fn drop(&mut self) {
+        if self.sift {
+            self.queue.sift_down(0);
+        }
+    }
+}
+
+/// A draining iterator over the values in the priority queue, in
+/// priority order, returned by `drain_sorted`
+pub struct DrainSorted<'a, T: 'a, F: 'a> {
+    queue: &'a mut PriorityQueue<T, F>,
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> Iterator for DrainSorted<'a, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.queue.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.queue.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> ExactSizeIterator for DrainSorted<'a, T, F> {}
+
+impl<'a, T, F> Drop for DrainSorted<'a, T, F> {
+    fn drop(&mut self) {
+        self.queue.storage.clear();
+    }
+}
+
+/// A non-consuming iterator over the values in the priority queue in
+/// priority order, returned by `iter_sorted`
+pub struct IterSorted<'a, T: 'a, F: 'a> {
+    queue: &'a PriorityQueue<T, F>,
+    /// Storage indices whose parents have been yielded but which haven't
+    /// been yielded themselves, kept as a heap ordered by the elements
+    /// they point to. Since every element has a higher priority than its
+    /// children, the best index in the frontier is always the next one.
+    frontier: Vec<usize>,
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> IterSorted<'a, T, F> {
+    fn higher(&self, a: usize, b: usize) -> bool {
+        let storage = &self.queue.storage;
+        (self.queue.comparator)(&storage[a], &storage[b]) == Ordering::Greater
+    }
+
+    fn push(&mut self, index: usize) {
+        let mut pos = self.frontier.len();
+        self.frontier.push(index);
+
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if !self.higher(self.frontier[pos], self.frontier[parent]) {
+                break;
+            }
+            self.frontier.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn pop(&mut self) -> usize {
+        let best = self.frontier.swap_remove(0);
+        let len = self.frontier.len();
+        let mut pos = 0;
+
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= len {
+                break;
+            }
+            if child + 1 < len && self.higher(self.frontier[child + 1], self.frontier[child]) {
+                child += 1;
+            }
+            if !self.higher(self.frontier[child], self.frontier[pos]) {
+                break;
+            }
+            self.frontier.swap(pos, child);
+            pos = child;
         }
+
+        best
+    }
+}
+
+impl<'a, T, F: Fn(&T, &T) -> Ordering> Iterator for IterSorted<'a, T, F> {
+    type Item = &'a T;
+
+    /// Skips elements marked as deleted, though their children are still
+    /// visited
+    fn next(&mut self) -> Option<&'a T> {
+        while !self.frontier.is_empty() {
+            let index = self.pop();
+            for child in [2 * index + 1, 2 * index + 2] {
+                if child < self.queue.storage.len() {
+                    self.push(child);
+                }
+            }
+
+            let value = &self.queue.storage[index];
+            if !self.queue.is_deleted(value) {
+                return Some(value);
+            }
+        }
+
+        None
     }
 }
 
 /// An non-comsuming iterator over the values in the priority queue 
-struct Iter<'a, T: 'a> {
+pub struct Iter<'a, T: 'a> {
     iter: slice::Iter<'a, T>,
 }
 
 /// A consuming iterator over the values in the priority queue
-struct IntoIter<T> {
+pub struct IntoIter<T> {
     iter: vec::IntoIter<T>,
 }
 
@@ -166,12 +652,29 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
+impl<'a, T, F> IntoIterator for &'a PriorityQueue<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
 // IntoIterator differs from Iterator by comsuming the original collection 
-impl<T> IntoIterator for PriorityQueue<T> {
+impl<T, F> IntoIterator for PriorityQueue<T, F> {
     type Item = T;
     type IntoIter = IntoIter<T>;
 
-    fn into_iter(self) -> IntoIter<T> {
+    /// Leaves out the elements marked as deleted
+    fn into_iter(mut self) -> IntoIter<T> {
+        if let Some(deleted) = &self.deleted {
+            self.storage.retain(|x| !deleted(x));
+        }
+
         IntoIter { iter: self.storage.into_iter() }
     }
 }
@@ -265,7 +768,357 @@ fn test_custom_peek() {
 }
 
 #[test]
-#[ignore]
+fn test_peek_mut() {
+    let mut pq = PriorityQueue::default();
+    assert!(pq.peek_mut().is_none());
+
+    for el in [3, 9, 4, 7] {
+        pq.insert(el);
+    }
+
+    *pq.peek_mut().unwrap() = 1;
+    assert_eq!(pq.peek(), Some(&7), "Lowered value sifted down");
+
+    {
+        let mut top = pq.peek_mut().unwrap();
+        *top += 10;
+        assert_eq!(*top, 17);
+    }
+    assert_eq!(pq.peek(), Some(&17));
+
+    assert_eq!(PeekMut::pop(pq.peek_mut().unwrap()), 17);
+    assert_eq!(pq.len(), 3);
+
+    let mut popped = vec![];
+    while let Some(el) = pq.pop() {
+        popped.push(el);
+    }
+    assert_eq!(popped, [4, 3, 1]);
+}
+
+#[test]
+fn test_custom_peek_mut() {
+    let mut pq = PriorityQueue::new_with(|a: &i64, b: &i64| b.cmp(a));
+    for el in [3, 9, 4, 7] {
+        pq.insert(el);
+    }
+
+    *pq.peek_mut().unwrap() = 8;
+    assert_eq!(pq.peek(), Some(&4));
+}
+
+#[test]
+fn test_into_sorted_vec() {
+    let pq: PriorityQueue<i32> = PriorityQueue::default();
+    assert_eq!(pq.into_sorted_vec(), []);
+
+    let mut pq = PriorityQueue::default();
+    for el in [6, 8, 10, 9, 1, 9, 9, 5] {
+        pq.insert(el);
+    }
+    assert_eq!(pq.into_sorted_vec(), [1, 5, 6, 8, 9, 9, 9, 10]);
+
+    let mut pq = PriorityQueue::new_with(|a: &i64, b: &i64| b.cmp(a));
+    for el in [6, 8, 10, 9, 1] {
+        pq.insert(el);
+    }
+    assert_eq!(pq.into_sorted_vec(), [10, 9, 8, 6, 1], "Sorted by the comparator");
+}
+
+#[test]
+fn test_drain_sorted() {
+    let mut pq = PriorityQueue::default();
+    for el in [6, 8, 10, 9, 1, 9, 9, 5] {
+        pq.insert(el);
+    }
+
+    let mut drain = pq.drain_sorted();
+    assert_eq!(drain.len(), 8);
+    assert_eq!(drain.by_ref().take(3).collect::<Vec<_>>(), [10, 9, 9]);
+    assert_eq!(drain.len(), 5);
+    drop(drain);
+    assert_eq!(pq.len(), 0, "Dropping the iterator drains the rest");
+
+    pq.insert(2);
+    pq.insert(7);
+    assert_eq!(pq.drain_sorted().collect::<Vec<_>>(), [7, 2]);
+    assert_eq!(pq.pop(), None);
+}
+
+#[test]
+fn test_capacity() {
+    let mut pq = PriorityQueue::with_capacity(10);
+    assert!(pq.capacity() >= 10);
+    assert_eq!(pq.len(), 0);
+
+    for el in 0..10 {
+        pq.insert(el);
+    }
+    assert!(pq.capacity() >= 10);
+    assert_eq!(pq.peek(), Some(&9), "Default comparator");
+
+    pq.reserve(100);
+    assert!(pq.capacity() >= 110);
+
+    pq.pop();
+    pq.shrink_to_fit();
+    assert!(pq.capacity() >= 9);
+    assert!(pq.capacity() < 110);
+}
+
+#[test]
+fn test_extend() {
+    let mut pq = PriorityQueue::new_with(|a: &i64, b: &i64| b.cmp(a));
+    pq.extend(vec![6, 8, 10]);
+    pq.extend(0..100);
+    pq.extend(Some(-1));
+    assert_eq!(pq.len(), 104);
+    assert_eq!(pq.pop(), Some(-1));
+    assert_eq!(pq.pop(), Some(0));
+    assert_eq!(pq.pop(), Some(1));
+
+    pq.extend(None);
+    assert_eq!(pq.len(), 101);
+}
+
+#[test]
+fn test_collect() {
+    let pq: PriorityQueue<_> = vec![6, 8, 10, 9, 1, 9, 9, 5].into_iter().collect();
+    assert_eq!(pq.peek(), Some(&10));
+    assert_eq!(pq.into_sorted_vec(), [1, 5, 6, 8, 9, 9, 9, 10]);
+
+    let pq = (0..1000).rev().collect::<PriorityQueue<_>>();
+    assert_eq!(pq.iter_sorted().take(3).copied().collect::<Vec<_>>(), [999, 998, 997]);
+}
+
+#[test]
+fn test_storage_access() {
+    let mut pq = PriorityQueue::default();
+    assert_eq!(pq.as_slice(), []);
+
+    for el in [3, 1, 4, 1, 5] {
+        pq.insert(el);
+    }
+
+    let heap = pq.as_slice();
+    assert_eq!(heap[0], 5);
+    for i in 1..heap.len() {
+        assert!(heap[(i - 1) / 2] >= heap[i], "Slice is in heap order");
+    }
+
+    let storage = pq.as_slice().to_vec();
+    assert_eq!(pq.into_vec(), storage);
+}
+
+#[test]
+fn test_without_ord() {
+    // floats aren't `Ord`, but a comparator can still order them
+    let mut pq = PriorityQueue::new_with(|a: &f64, b: &f64| a.total_cmp(b));
+    for el in [2.5, -1.0, 7.25] {
+        pq.insert(el);
+    }
+
+    *pq.peek_mut().unwrap() = 0.0;
+    assert_eq!(pq.drain_sorted().collect::<Vec<_>>(), [2.5, 0.0, -1.0]);
+}
+
+#[test]
+fn test_clone_debug_eq() {
+    let pq: PriorityQueue<_> = vec![3, 1, 2].into_iter().collect();
+    let mut copy = pq.clone();
+    assert_eq!(format!("{:?}", pq), "[3, 1, 2]");
+
+    let mut reversed = PriorityQueue::new_with(|a: &i32, b: &i32| b.cmp(a));
+    reversed.extend(vec![2, 3, 1]);
+    assert_eq!(pq, reversed, "Equal as multisets");
+    assert_eq!(pq, copy);
+
+    copy.insert(3);
+    assert_ne!(pq, copy);
+    copy.remove_where(|x| *x == 1);
+    assert_ne!(pq, copy, "Same length, different elements");
+    assert_eq!(pq.len(), 3);
+}
+
+#[test]
+fn test_closure_comparator() {
+    // priorities looked up in a table the comparator borrows
+    let deadlines = [40, 10, 30, 20];
+    let mut pq = PriorityQueue::new_with(|a: &usize, b: &usize| deadlines[*b].cmp(&deadlines[*a]));
+    pq.extend(0..deadlines.len());
+
+    assert_eq!(pq.peek(), Some(&1), "Earliest deadline first");
+    assert_eq!(pq.iter_sorted().copied().collect::<Vec<_>>(), [1, 3, 2, 0]);
+    assert_eq!(pq.drain_sorted().collect::<Vec<_>>(), [1, 3, 2, 0]);
+
+    let min_first = true;
+    let mut pq = PriorityQueue::new_with(move |a: &i32, b: &i32| {
+        if min_first { b.cmp(a) } else { a.cmp(b) }
+    });
+    pq.extend(vec![3, 1, 2]);
+    assert_eq!(PeekMut::pop(pq.peek_mut().unwrap()), 1);
+    assert_eq!(pq.into_sorted_vec(), [3, 2]);
+}
+
+#[test]
+fn test_mark_deleted() {
+    let mut pq: PriorityQueue<i32> = (1..=10).collect();
+    pq.mark_deleted(|&x| x % 3 == 0 || x > 8);
+    assert_eq!(pq.len(), 10, "Deleted elements are dropped lazily");
+    assert_eq!(pq.peek(), Some(&8), "Peek skips deleted elements");
+    assert_eq!(pq.iter_sorted().copied().collect::<Vec<_>>(), [8, 7, 5, 4, 2, 1]);
+    assert_eq!(pq.pop(), Some(8));
+    assert_eq!(pq.len(), 7, "10 and 9 were dropped on the way");
+
+    pq.insert(12);
+    pq.insert(11);
+    assert_eq!(pq.peek_mut().map(|top| *top), Some(7), "Later inserts are deleted too");
+
+    pq.purge_deleted();
+    assert_eq!(pq.len(), 5);
+    assert_eq!(pq.clone().into_sorted_vec(), [1, 2, 4, 5, 7]);
+    assert_eq!(pq.drain_sorted().len(), 5);
+}
+
+#[test]
+fn test_unmark_deleted() {
+    let mut pq: PriorityQueue<i32> = (1..=5).collect();
+    pq.mark_deleted(|&x| x == 5);
+    assert_eq!(pq.peek(), Some(&4));
+
+    pq.mark_deleted(|&x| x == 4);
+    assert_eq!(pq.peek(), Some(&5), "A new predicate replaces the old one");
+
+    pq.unmark_deleted();
+    pq.insert(4);
+    assert_eq!(pq.into_sorted_vec(), [1, 2, 3, 4, 4, 5]);
+}
+
+#[test]
+fn test_deleted_elements_stay_out() {
+    let mut pq: PriorityQueue<i32> = (1..=3).collect();
+    pq.mark_deleted(|&x| x == 3);
+
+    let rest: PriorityQueue<i32> = (1..=2).collect();
+    assert_eq!(pq, rest);
+    assert_ne!(pq, (1..=3).collect::<PriorityQueue<i32>>());
+    assert_eq!(format!("{:?}", pq), "[2, 1]");
+
+    let mut items: Vec<_> = pq.clone().into_iter().collect();
+    items.sort();
+    assert_eq!(items, [1, 2]);
+    assert_eq!(pq.clone().into_vec(), [2, 1]);
+
+    let mut other = PriorityQueue::default();
+    other.append(&mut pq);
+    assert_eq!(other.into_sorted_vec(), [1, 2], "Appending drops deleted elements");
+}
+
+#[test]
+fn test_mark_deleted_stale_entries() {
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    // (priority, task) entries go stale when a task is cancelled
+    let cancelled = Arc::new(Mutex::new(HashSet::new()));
+    let mut pq = PriorityQueue::default();
+    pq.extend(vec![(5, 'a'), (3, 'b'), (4, 'c'), (1, 'd'), (2, 'e')]);
+
+    let deleted = Arc::clone(&cancelled);
+    pq.mark_deleted(move |&(_, task)| deleted.lock().unwrap().contains(&task));
+
+    cancelled.lock().unwrap().extend(['a', 'c']);
+    assert_eq!(pq.pop(), Some((3, 'b')));
+    cancelled.lock().unwrap().insert('e');
+    assert_eq!(pq.drain_sorted().collect::<Vec<_>>(), [(1, 'd')]);
+}
+
+#[test]
+fn test_remove_where() {
+    let mut pq = PriorityQueue::default();
+    for el in [6, 8, 10, 9, 1, 9, 9, 5] {
+        pq.insert(el);
+    }
+
+    assert_eq!(pq.remove_where(|x| *x == 8), Some(8));
+    assert_eq!(pq.remove_where(|x| *x == 8), None);
+    assert_eq!(pq.remove_where(|x| *x == 10), Some(10), "Removing the priority value");
+    assert_eq!(pq.remove_where(|x| *x < 6), Some(1));
+    assert_eq!(pq.len(), 5);
+    assert_eq!(pq.into_sorted_vec(), [5, 6, 9, 9, 9]);
+
+    // the element moved into the hole can belong higher up the heap
+    let mut pq = PriorityQueue::default();
+    for el in [100, 50, 90, 10, 20, 80, 85] {
+        pq.insert(el);
+    }
+    assert_eq!(pq.remove_where(|x| *x == 10), Some(10));
+    assert_eq!(pq.pop(), Some(100));
+    assert_eq!(pq.pop(), Some(90));
+    assert_eq!(pq.pop(), Some(85));
+}
+
+#[test]
+fn test_retain() {
+    let mut pq = PriorityQueue::default();
+    for el in 0..20 {
+        pq.insert(el);
+    }
+
+    pq.retain(|x| x % 3 == 0);
+    assert_eq!(pq.len(), 7);
+    assert_eq!(pq.peek(), Some(&18));
+    assert_eq!(pq.into_sorted_vec(), [0, 3, 6, 9, 12, 15, 18]);
+}
+
+#[test]
+fn test_append() {
+    let mut pq = PriorityQueue::default();
+    let mut other = PriorityQueue::default();
+    for el in [6, 8, 10] {
+        pq.insert(el);
+    }
+    for el in [9, 1, 9, 9, 5, 12, 3] {
+        other.insert(el);
+    }
+
+    pq.append(&mut other);
+    assert_eq!(pq.len(), 10);
+    assert_eq!(other.len(), 0);
+    assert_eq!(pq.into_sorted_vec(), [1, 3, 5, 6, 8, 9, 9, 9, 10, 12]);
+
+    // a small batch is inserted rather than rebuilding
+    let mut pq = PriorityQueue::default();
+    for el in 0..100 {
+        pq.insert(el);
+    }
+    let mut other = PriorityQueue::default();
+    other.insert(50);
+    other.insert(200);
+    pq.append(&mut other);
+    assert_eq!(pq.peek(), Some(&200));
+    assert_eq!(pq.into_sorted_vec(), {
+        let mut expected: Vec<_> = (0..100).chain([50, 200]).collect();
+        expected.sort();
+        expected
+    });
+}
+
+#[test]
+fn test_custom_append() {
+    let mut pq = PriorityQueue::new_with(|a: &i64, b: &i64| b.cmp(a));
+    let mut other = PriorityQueue::default();
+    pq.insert(4);
+    for el in [6, 2, 8] {
+        other.insert(el);
+    }
+
+    pq.append(&mut other);
+    assert_eq!(pq.pop(), Some(2), "Merged queue keeps its own comparator");
+    assert_eq!(pq.pop(), Some(4));
+}
+
+#[test]
 fn test_default_iterator_correctness() {
     let mut pq = PriorityQueue::default();
     let values = vec![6, 8, 10, 9, 1, 9, 9, 5];
@@ -276,13 +1129,12 @@ fn test_default_iterator_correctness() {
         pq.insert(el);
     }
 
-    let collected = pq.iter().map(|x| *x).collect::<Vec<_>>();
+    let collected = pq.iter_sorted().copied().collect::<Vec<_>>();
 
     assert_eq!(collected, expected);
 }
 
 #[test]
-#[ignore]
 fn test_custom_iterator_correctness() {
     let mut pq = PriorityQueue::new_with(|a: &i64, b: &i64| b.cmp(a));
     let values = vec![6, 8, 10, 9, 1, 9, 9, 5];
@@ -293,7 +1145,7 @@ fn test_custom_iterator_correctness() {
         pq.insert(el);
     }
 
-    let collected = pq.iter().map(|x| *x).collect::<Vec<_>>();
+    let collected = pq.iter_sorted().copied().collect::<Vec<_>>();
 
     assert_eq!(collected, expected);
 }
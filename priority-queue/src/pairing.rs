@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+use std::mem;
+
+/// A priority queue backed by a pairing heap: a tree where every node
+/// has a higher priority than its children. Unlike the binary heap in
+/// `PriorityQueue`, two pairing heaps can be melded in constant time, so
+/// it suits callers that merge queues often. Popping takes amortized
+/// O(log n) time.
+pub struct PairingHeap<T> {
+    root: Option<Box<Node<T>>>,
+    len: usize,
+    /// A generic comparator function that returns an Ordering of the
+    /// elements in the heap
+    comparator: fn(&T, &T) -> Ordering,
+}
+
+struct Node<T> {
+    element: T,
+    children: Vec<Box<Node<T>>>,
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    /// Default PairingHeap is a max heap
+    fn default() -> Self {
+        PairingHeap::new_with(|a: &T, b: &T| a.cmp(b))
+    }
+}
+
+impl<T> PairingHeap<T> {
+    /// New PairingHeap instance with specified comparator
+    pub fn new_with(comparator: fn(&T, &T) -> Ordering) -> Self {
+        PairingHeap {
+            root: None,
+            len: 0,
+            comparator,
+        }
+    }
+
+    /// Returns a reference to the priority value, which is always the
+    /// root of the heap
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|node| &node.element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Takes ownership of value and inserts it
+    pub fn insert(&mut self, value: T) {
+        let node = Box::new(Node {
+            element: value,
+            children: Vec::new(),
+        });
+
+        self.root = Some(match self.root.take() {
+            Some(root) => self.link(root, node),
+            None => node,
+        });
+        self.len += 1;
+    }
+
+    /// Removes and returns the owned priority value. The root's children
+    /// are linked in pairs from left to right, and the pairs are then
+    /// linked into one tree from right to left.
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let Node { element, children } = *root;
+
+        let mut pairs = Vec::with_capacity(children.len().div_ceil(2));
+        let mut children = children.into_iter();
+        while let Some(first) = children.next() {
+            pairs.push(match children.next() {
+                Some(second) => self.link(first, second),
+                None => first,
+            });
+        }
+
+        self.root = pairs.pop().map(|last| {
+            pairs
+                .into_iter()
+                .rev()
+                .fold(last, |merged, pair| self.link(merged, pair))
+        });
+        self.len -= 1;
+
+        Some(element)
+    }
+
+    /// Moves all the elements of `other` into this heap in constant time,
+    /// leaving `other` empty. The merged heap keeps this heap's
+    /// comparator.
+    pub fn meld(&mut self, other: &mut Self) {
+        let other_root = match other.root.take() {
+            Some(root) => root,
+            None => return,
+        };
+
+        self.root = Some(match self.root.take() {
+            Some(root) => self.link(root, other_root),
+            None => other_root,
+        });
+        self.len += mem::replace(&mut other.len, 0);
+    }
+
+    /// Makes the root with the lower priority a child of the other one
+    fn link(&self, mut a: Box<Node<T>>, mut b: Box<Node<T>>) -> Box<Node<T>> {
+        if (self.comparator)(&a.element, &b.element) == Ordering::Less {
+            b.children.push(a);
+            b
+        } else {
+            a.children.push(b);
+            a
+        }
+    }
+}
+
+impl<T> Drop for PairingHeap<T> {
+    /// Frees the nodes one at a time, since a heap built from ascending
+    /// inserts is a single path that would overflow the stack if it were
+    /// dropped recursively
+    fn drop(&mut self) {
+        let mut unvisited: Vec<_> = self.root.take().into_iter().collect();
+
+        while let Some(mut node) = unvisited.pop() {
+            unvisited.append(&mut node.children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn drain<T>(heap: &mut PairingHeap<T>) -> Vec<T> {
+        let mut popped = vec![];
+        while let Some(el) = heap.pop() {
+            popped.push(el);
+        }
+
+        popped
+    }
+
+    #[test]
+    fn test_insert_pop() {
+        let mut heap = PairingHeap::default();
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+
+        for el in [6, 8, 10, 9, 1, 9, 9, 5] {
+            heap.insert(el);
+        }
+        assert_eq!(heap.len(), 8);
+        assert_eq!(heap.peek(), Some(&10));
+
+        assert_eq!(drain(&mut heap), [10, 9, 9, 9, 8, 6, 5, 1]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_custom_comparator() {
+        let mut heap = PairingHeap::new_with(|a: &i64, b: &i64| b.cmp(a));
+        for el in [6, 8, 10, 9, 1] {
+            heap.insert(el);
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(drain(&mut heap), [1, 6, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_meld() {
+        let mut a = PairingHeap::default();
+        let mut b = PairingHeap::default();
+        for el in [4, 7, 1] {
+            a.insert(el);
+        }
+        for el in [8, 2, 5, 3] {
+            b.insert(el);
+        }
+
+        a.meld(&mut b);
+        assert_eq!(a.len(), 7);
+        assert!(b.is_empty());
+        assert_eq!(b.peek(), None);
+
+        b.meld(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(drain(&mut b), [8, 7, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_drop_deep_heap() {
+        let mut heap = PairingHeap::default();
+        for el in 0..500_000 {
+            heap.insert(el);
+        }
+        assert_eq!(heap.peek(), Some(&499_999));
+    }
+}
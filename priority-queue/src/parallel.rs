@@ -0,0 +1,176 @@
+use rayon::iter::{IntoParallelIterator, ParallelExtend};
+use std::cmp::{self, Ordering};
+use std::mem;
+
+use crate::PriorityQueue;
+
+/// Subtrees with at most this many elements are heapified on the
+/// current thread, since spawning tasks for them costs more than it saves
+const SEQUENTIAL_THRESHOLD: usize = 1 << 14;
+
+impl<T: Ord + Send> PriorityQueue<T> {
+    /// Builds a max heap out of the elements of the vec, heapifying
+    /// sibling subtrees in parallel. Use `par_extend` on an empty queue
+    /// to build one with a custom comparator instead.
+    pub fn par_from_vec(storage: Vec<T>) -> Self {
+        let mut pq = PriorityQueue {
+            storage,
+            ..Self::default()
+        };
+        pq.par_rebuild();
+
+        pq
+    }
+}
+
+impl<T, F> PriorityQueue<T, F>
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    /// Like `extend`, but collects the elements in parallel and rebuilds
+    /// the heap in parallel when that is cheaper than inserting them
+    pub fn par_extend<I>(&mut self, iter: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let start = self.len();
+        self.storage.par_extend(iter);
+
+        if self.prefers_rebuild(start) {
+            self.par_rebuild();
+        } else {
+            for pos in start..self.len() {
+                self.bubble_up(0, pos);
+            }
+        }
+    }
+
+    /// Restores the heap property over the whole storage vec. The
+    /// elements of a subtree aren't contiguous in storage, but on each
+    /// level of the tree they are, so the storage is split into one
+    /// slice per level and every subtree borrows its half of each of the
+    /// levels below its root.
+    fn par_rebuild(&mut self) {
+        let mut levels = Vec::new();
+        let mut rest = &mut self.storage[..];
+        let mut width = 1;
+
+        while !rest.is_empty() {
+            let (level, tail) = rest.split_at_mut(cmp::min(width, rest.len()));
+            levels.push(level);
+            rest = tail;
+            width *= 2;
+        }
+
+        heapify(&mut levels, &self.comparator);
+    }
+}
+
+/// Heapifies the subtree whose root is the only element of the first
+/// level, by heapifying the subtrees of its two children first and then
+/// sifting the root down
+fn heapify<T, F>(levels: &mut [&mut [T]], comparator: &F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let size: usize = levels.iter().map(|level| level.len()).sum();
+
+    if size <= SEQUENTIAL_THRESHOLD {
+        for depth in (0..levels.len().saturating_sub(1)).rev() {
+            for pos in 0..levels[depth].len() {
+                sift_down(levels, depth, pos, comparator);
+            }
+        }
+        return;
+    }
+
+    {
+        // level `depth` of this subtree holds 2^depth elements, so the
+        // left child's subtree gets the first 2^(depth - 1) of them
+        let (mut left, mut right): (Vec<_>, Vec<_>) = levels[1..]
+            .iter_mut()
+            .enumerate()
+            .map(|(depth, level)| level.split_at_mut(cmp::min(1 << depth, level.len())))
+            .unzip();
+
+        rayon::join(
+            || heapify(&mut left, comparator),
+            || heapify(&mut right, comparator),
+        );
+    }
+
+    sift_down(levels, 0, 0, comparator);
+}
+
+/// Swaps the element at `pos` on the given level down the subtree with
+/// its higher-priority child until it reaches an appropriate spot
+fn sift_down<T, F>(levels: &mut [&mut [T]], mut depth: usize, mut pos: usize, comparator: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    while depth + 1 < levels.len() {
+        let (upper, lower) = levels.split_at_mut(depth + 1);
+        let parent = &mut upper[depth][pos];
+        let children = &mut lower[0];
+
+        let mut child = 2 * pos;
+        if child >= children.len() {
+            break;
+        }
+        if child + 1 < children.len()
+            && comparator(&children[child], &children[child + 1]) != Ordering::Greater
+        {
+            child += 1;
+        }
+
+        if comparator(parent, &children[child]) == Ordering::Less {
+            mem::swap(parent, &mut children[child]);
+            depth += 1;
+            pos = child;
+        } else {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn is_heap<T, F: Fn(&T, &T) -> Ordering>(pq: &PriorityQueue<T, F>) -> bool {
+        let storage = pq.as_slice();
+        (1..storage.len()).all(|pos| (pq.comparator)(&storage[(pos - 1) / 2], &storage[pos]) != Ordering::Less)
+    }
+
+    /// Scrambled but reproducible input, large enough to be split up
+    fn scrambled(len: u64) -> Vec<u64> {
+        (0..len).map(|x| x.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 40).collect()
+    }
+
+    #[test]
+    fn test_par_from_vec() {
+        for &len in &[0, 1, 2, 1000, 100_000, 262_143, 262_144] {
+            let input = scrambled(len);
+            let pq = PriorityQueue::par_from_vec(input.clone());
+            assert!(is_heap(&pq), "Heap order broken for {} elements", len);
+
+            let mut expected = input;
+            expected.sort();
+            assert_eq!(pq.into_sorted_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_par_extend() {
+        let mut pq = PriorityQueue::new_with(|a: &u64, b: &u64| b.cmp(a));
+        pq.par_extend(scrambled(200_000));
+        assert!(is_heap(&pq));
+        assert_eq!(pq.len(), 200_000);
+
+        pq.par_extend(vec![u64::MAX, 0]);
+        assert!(is_heap(&pq));
+        assert_eq!(pq.peek(), Some(&0), "Min heap comparator kept");
+    }
+}